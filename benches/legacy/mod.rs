@@ -0,0 +1,153 @@
+// the tokenizer as it was before it scanned buffers: one `read` call per byte,
+// a byte of lookahead and fields built up one byte at a time. only the parts
+// that matter for speed are kept
+use std::io::{self, BufReader, Read};
+
+use rust_csv_parser::{ByteRecord, Position, Record};
+
+pub struct LegacyParser<R: Read> {
+    reader: BufReader<R>,
+    peek: Option<u8>,
+    started: bool,
+    position: Position,
+    last_cr: bool,
+}
+
+impl<R: Read> LegacyParser<R> {
+    pub fn new(reader: R) -> Self {
+        LegacyParser {
+            reader: BufReader::new(reader),
+            peek: None,
+            started: false,
+            position: Position::new(0, 1, 1, 0),
+            last_cr: false,
+        }
+    }
+
+    fn next_byte_(&mut self) -> io::Result<Option<u8>> {
+        let mut buf = [0u8; 1];
+        if self.reader.read(&mut buf)? == 0 {
+            return Ok(None);
+        }
+        let b = buf[0];
+        let (line, column) = match b {
+            b'\n' if self.last_cr => (self.position.line(), 1),
+            b'\r' | b'\n' => (self.position.line() + 1, 1),
+            0x80..=0xBF => (self.position.line(), self.position.column()),
+            _ => (self.position.line(), self.position.column() + 1),
+        };
+        self.last_cr = b == b'\r';
+        self.position = Position::new(0, line, column, self.position.byte() + 1);
+        Ok(Some(b))
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if !self.started {
+            self.started = true;
+            self.peek = self.next_byte_()?;
+        }
+        let ret = self.peek;
+        self.peek = self.next_byte_()?;
+        Ok(ret)
+    }
+
+    fn skip_crlf(&mut self, b: u8) -> io::Result<()> {
+        if b == b'\r' && self.peek == Some(b'\n') {
+            self.next_byte()?;
+        }
+        Ok(())
+    }
+
+    fn parse_escaped(&mut self) -> io::Result<(Vec<u8>, bool)> {
+        let mut curr = Vec::new();
+        loop {
+            match self.next_byte()? {
+                Some(b'"') => match self.peek {
+                    Some(b'"') => {
+                        curr.push(b'"');
+                        self.next_byte()?;
+                    }
+                    Some(b',') => {
+                        self.next_byte()?;
+                        return Ok((curr, false));
+                    }
+                    Some(b'\r' | b'\n') | None => return Ok((curr, true)),
+                    Some(_) => return Err(io::ErrorKind::InvalidData.into()),
+                },
+                Some(b) => curr.push(b),
+                None => return Err(io::ErrorKind::InvalidData.into()),
+            }
+        }
+    }
+
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let mut b = match self.next_byte()? {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+        let mut record = ByteRecord::new();
+        loop {
+            match b {
+                b'\r' | b'\n' => {
+                    record.push_field(b"");
+                    self.skip_crlf(b)?;
+                    break;
+                }
+                b'"' => {
+                    let (field, end) = self.parse_escaped()?;
+                    record.push_field(&field);
+                    if end {
+                        if let Some(peek @ (b'\r' | b'\n')) = self.peek {
+                            self.next_byte()?;
+                            self.skip_crlf(peek)?;
+                        }
+                        break;
+                    }
+                }
+                _ => {
+                    let mut field = Vec::new();
+                    let mut last = Some(b);
+                    while let Some(b) = last {
+                        if b == b',' || b == b'\r' || b == b'\n' {
+                            break;
+                        }
+                        if b == b'"' {
+                            return Err(io::ErrorKind::InvalidData.into());
+                        }
+                        field.push(b);
+                        last = self.next_byte()?;
+                    }
+                    record.push_field(&field);
+                    match last {
+                        Some(b',') => match self.peek {
+                            Some(b'\r' | b'\n') | None => {
+                                record.push_field(b"");
+                                if let Some(peek) = self.peek {
+                                    self.next_byte()?;
+                                    self.skip_crlf(peek)?;
+                                }
+                                break;
+                            }
+                            Some(_) => {}
+                        },
+                        Some(last) => {
+                            self.skip_crlf(last)?;
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            b = match self.next_byte()? {
+                Some(b) => b,
+                None => {
+                    record.push_field(b"");
+                    break;
+                }
+            };
+        }
+        Record::from_byte_record(record)
+            .map(Some)
+            .map_err(io::Error::from)
+    }
+}
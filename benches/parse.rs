@@ -0,0 +1,122 @@
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rust_csv_parser::{BorrowedRecord, CsvParser, ParallelParser, Record, SliceParser};
+
+mod legacy;
+
+// a mix of plain, quoted and escaped fields, about 4 MB
+fn input() -> String {
+    let mut input = String::from("id,name,city,score,note,email,active,created\r\n");
+    for i in 0..40_000 {
+        input.push_str(&format!(
+            "{i},user number {i},\"Oslo, Norway\",{}.{},\"said \"\"hi\"\" twice\",user{i}@example.com,true,2024-01-{:02}\r\n",
+            i % 100,
+            i % 7,
+            i % 28 + 1
+        ));
+    }
+    input
+}
+
+fn parse(c: &mut Criterion) {
+    let input = input();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+
+    group.bench_function("legacy byte reads", |b| {
+        b.iter(|| {
+            let mut parser = legacy::LegacyParser::new(Cursor::new(input.as_bytes()));
+            let mut count = 0;
+            while let Some(record) = parser.next_record().unwrap() {
+                count += record.len();
+            }
+            count
+        })
+    });
+
+    group.bench_function("records", |b| {
+        b.iter(|| {
+            let parser = CsvParser::new(Cursor::new(input.as_bytes()));
+            parser.map(|record| record.unwrap().len()).sum::<usize>()
+        })
+    });
+
+    group.bench_function("byte records", |b| {
+        b.iter(|| {
+            let mut parser = CsvParser::new(Cursor::new(input.as_bytes()));
+            parser
+                .byte_records()
+                .map(|record| record.unwrap().len())
+                .sum::<usize>()
+        })
+    });
+
+    group.bench_function("read_record into one record", |b| {
+        b.iter(|| {
+            let mut parser = CsvParser::new(Cursor::new(input.as_bytes()));
+            let mut record = Record::new();
+            let mut count = 0;
+            while parser.read_record(&mut record).unwrap() {
+                count += record.len();
+            }
+            count
+        })
+    });
+
+    group.bench_function("borrowed records", |b| {
+        b.iter(|| {
+            let parser = SliceParser::new(&input);
+            parser.map(|record| record.unwrap().len()).sum::<usize>()
+        })
+    });
+
+    group.bench_function("borrowed read_record into one record", |b| {
+        b.iter(|| {
+            let mut parser = SliceParser::new(&input);
+            let mut record = BorrowedRecord::default();
+            let mut count = 0;
+            while parser.read_record(&mut record).unwrap() {
+                count += record.len();
+            }
+            count
+        })
+    });
+
+    group.finish();
+}
+
+// the same input read from a file, by one parser and split across threads
+fn parse_file(c: &mut Criterion) {
+    let input = input().repeat(4);
+    let path =
+        std::env::temp_dir().join(format!("rust_csv_parser_bench_{}.csv", std::process::id()));
+    std::fs::write(&path, &input).unwrap();
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut group = c.benchmark_group("parse file");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("records", |b| {
+        b.iter(|| {
+            let parser = CsvParser::from_path(&path).unwrap();
+            parser.map(|record| record.unwrap().len()).sum::<usize>()
+        })
+    });
+
+    group.bench_function(format!("parallel records on {threads} threads"), |b| {
+        b.iter(|| {
+            let parser = ParallelParser::from_path(&path)
+                .unwrap()
+                .threads(threads)
+                .chunk_size(1024 * 1024);
+            parser.map(|record| record.unwrap().len()).sum::<usize>()
+        })
+    });
+
+    group.finish();
+    let _ = std::fs::remove_file(path);
+}
+
+criterion_group!(benches, parse, parse_file);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{CsvError, Options, PushParser, Record, Result, Utf8Mode};
+
+// reads records from an `AsyncRead` without blocking. what is read is fed to
+// a `PushParser` so the records come out the same as `CsvParser` gives them,
+// the input has to be UTF-8 and there is no recover mode
+pub struct AsyncCsvParser<R: AsyncRead + Unpin> {
+    reader: R,
+    buffer: Box<[u8]>,
+    parser: PushParser,
+    records: std::vec::IntoIter<Record>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncCsvParser<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncCsvParser {
+            reader,
+            buffer: vec![0; 64 * 1024].into_boxed_slice(),
+            parser: PushParser::new(),
+            records: Vec::new().into_iter(),
+        }
+    }
+
+    fn set(mut self, f: impl FnOnce(PushParser) -> PushParser) -> Self {
+        self.parser = f(self.parser);
+        self
+    }
+
+    options_setters!();
+
+    fn set_options(self, f: impl FnOnce(Options) -> Options) -> Self {
+        self.set(|parser| parser.set_options(f))
+    }
+
+    pub fn utf8(self, mode: Utf8Mode) -> Self {
+        self.set(|parser| parser.utf8(mode))
+    }
+
+    pub fn warnings(&self) -> &[CsvError] {
+        self.parser.warnings()
+    }
+
+    pub fn preamble(&self) -> &[String] {
+        self.parser.preamble()
+    }
+
+    pub async fn headers(&mut self) -> Result<Option<&[String]>> {
+        poll_fn(|cx| self.poll_headers(cx)).await?;
+        Ok(self.parser.headers())
+    }
+
+    fn poll_headers(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while self.parser.wants_headers() {
+            ready!(self.poll_chunk(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    // reads the next chunk of input and feeds it to the parser
+    fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        // an error that came after the records of the last chunk
+        if let Some(error) = self.parser.take_error() {
+            return Poll::Ready(Err(error));
+        }
+        let mut buf = ReadBuf::new(&mut self.buffer);
+        if let Err(error) = ready!(Pin::new(&mut self.reader).poll_read(cx, &mut buf)) {
+            return Poll::Ready(Err(self.parser.fail(error)));
+        }
+        let records = match buf.filled() {
+            [] => self.parser.finish()?,
+            input => self.parser.feed(input)?,
+        };
+        self.records = records.into_iter();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + Unpin> fmt::Debug for AsyncCsvParser<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncCsvParser")
+            .field("parser", &self.parser)
+            .field("records", &self.records.len())
+            .finish()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncCsvParser<R> {
+    type Item = Result<Record>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(record) = this.records.next() {
+                return Poll::Ready(Some(Ok(record)));
+            }
+            if this.parser.is_done() {
+                return Poll::Ready(None);
+            }
+            if let Err(err) = ready!(this.poll_chunk(cx)) {
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::tokenizer::{CsvCore, Fields};
+use crate::{check_headers, CsvError, Dialect, Options, Position, RaggedRows, Record, Result};
+
+// a record whose fields point into the input, only a field that the
+// tokenizer had to change (a quoted field with escapes in it) is owned
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BorrowedRecord<'a> {
+    fields: Vec<Cow<'a, str>>,
+    position: Position,
+}
+
+impl<'a> BorrowedRecord<'a> {
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.fields.get(i).map(|field| field.as_ref())
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.fields.iter().map(|field| field.as_ref())
+    }
+
+    pub fn fields(&self) -> &[Cow<'a, str>] {
+        &self.fields
+    }
+
+    pub fn into_fields(self) -> Vec<Cow<'a, str>> {
+        self.fields
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+}
+
+impl From<BorrowedRecord<'_>> for Record {
+    fn from(record: BorrowedRecord<'_>) -> Self {
+        let mut ret = Record::from(record.fields);
+        ret.set_position(record.position);
+        ret
+    }
+}
+
+impl<T: AsRef<str>> PartialEq<[T]> for BorrowedRecord<'_> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(field, other)| field == other.as_ref())
+    }
+}
+
+impl<T: AsRef<str>> PartialEq<Vec<T>> for BorrowedRecord<'_> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self == other.as_slice()
+    }
+}
+
+// where the fields of a record are in the input. a field the tokenizer had
+// to change, one with an escape or a doubled quote in it, is copied to `owned`
+#[derive(Debug)]
+struct Spans<'a> {
+    input: &'a [u8],
+    fields: Vec<Span>,
+    owned: Vec<u8>,
+    position: Position,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    // where the field starts in the input
+    start: usize,
+    // the field in the input, or in `owned` once it was copied
+    range: Range<usize>,
+    owned: bool,
+}
+
+impl Spans<'_> {
+    // adds bytes that are not in the input as they are, the field is copied
+    // out of the input first
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let Some(span) = self.fields.last_mut() else {
+            return;
+        };
+        if !span.owned {
+            let from = self.owned.len();
+            self.owned
+                .extend_from_slice(&self.input[span.range.clone()]);
+            span.range = from..self.owned.len();
+            span.owned = true;
+        }
+        self.owned.extend_from_slice(bytes);
+        span.range.end = self.owned.len();
+    }
+}
+
+impl Fields for Spans<'_> {
+    fn clear(&mut self) {
+        self.fields.clear();
+        self.owned.clear();
+    }
+
+    fn start_field(&mut self, at: u64) {
+        let at = at as usize;
+        self.fields.push(Span {
+            start: at,
+            range: at..at,
+            owned: false,
+        });
+    }
+
+    fn extend_field(&mut self, bytes: &[u8], at: u64) {
+        match self.fields.last_mut() {
+            Some(span) if !span.owned && span.range.end == at as usize => {
+                span.range.end += bytes.len();
+            }
+            _ => self.push_bytes(bytes),
+        }
+    }
+
+    fn push_byte(&mut self, b: u8) {
+        self.push_bytes(&[b]);
+    }
+
+    fn end_field(&mut self, trim_space: bool, all_whitespace_empty: bool) {
+        let Some(span) = self.fields.last_mut() else {
+            return;
+        };
+        let field = match span.owned {
+            true => &self.owned[span.range.clone()],
+            false => &self.input[span.range.clone()],
+        };
+        let trimmed = field.trim_ascii();
+        if all_whitespace_empty && trimmed.is_empty() {
+            span.range.end = span.range.start;
+        } else if trim_space && trimmed.len() != field.len() {
+            let lead = field.len() - field.trim_ascii_start().len();
+            span.start += lead;
+            span.range = span.range.start + lead..span.range.start + lead + trimmed.len();
+        }
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+}
+
+// parses input that is already in memory, a `&str`, `&[u8]` or a mapped
+// file, without copying it. the input has to be UTF-8
+#[derive(Debug)]
+pub struct SliceParser<'a> {
+    input: &'a [u8],
+    // the input as text when all of it is valid UTF-8
+    text: Option<&'a str>,
+    offset: usize,
+    options: Options,
+    core: CsvCore,
+    spans: Spans<'a>,
+    // the field count of the last record, new records are sized for it
+    capacity: usize,
+    record_field_count: Option<u64>,
+    headers: Option<Vec<Cow<'a, str>>>,
+    warnings: Vec<CsvError>,
+    done: bool,
+}
+
+impl<'a> SliceParser<'a> {
+    pub fn new<T: AsRef<[u8]> + ?Sized>(input: &'a T) -> Self {
+        let input = input.as_ref();
+        let input = input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input);
+        SliceParser {
+            input,
+            text: std::str::from_utf8(input).ok(),
+            offset: 0,
+            options: Options::default(),
+            core: CsvCore::new(Dialect::default()),
+            spans: Spans {
+                input,
+                fields: Vec::new(),
+                owned: Vec::new(),
+                position: Position::default(),
+            },
+            capacity: 0,
+            record_field_count: None,
+            headers: None,
+            warnings: Vec::new(),
+            done: false,
+        }
+    }
+
+    options_setters!();
+
+    fn set_options(mut self, f: impl FnOnce(Options) -> Options) -> Self {
+        self.options = f(self.options);
+        self.core = self.options.core();
+        self
+    }
+
+    pub fn preamble(&self) -> &[String] {
+        self.core.preamble()
+    }
+
+    pub fn warnings(&self) -> &[CsvError] {
+        &self.warnings
+    }
+
+    pub fn headers(&mut self) -> Result<Option<&[Cow<'a, str>]>> {
+        self.read_headers()?;
+        Ok(self.headers.as_deref())
+    }
+
+    fn read_headers(&mut self) -> Result<()> {
+        if !self.options.has_header || self.headers.is_some() {
+            return Ok(());
+        }
+        let mut names = BorrowedRecord::default();
+        if !self.read_fields(&mut names)? {
+            self.headers = Some(Vec::new());
+            return Ok(());
+        }
+        check_headers(&names.fields, names.position)?;
+        self.headers = Some(names.fields);
+        Ok(())
+    }
+
+    // like `next` but the fields are put in `record`, its buffer is reused
+    // so only a field with an escape in it allocates. returns false once
+    // there are no records left
+    pub fn read_record(&mut self, record: &mut BorrowedRecord<'a>) -> Result<bool> {
+        if self.done {
+            record.fields.clear();
+            return Ok(false);
+        }
+        let read = self.read_headers().and_then(|_| self.read_fields(record));
+        if !matches!(read, Ok(true)) {
+            self.done = true;
+        }
+        read
+    }
+
+    fn next_record(&mut self) -> Result<Option<BorrowedRecord<'a>>> {
+        let mut record = BorrowedRecord {
+            fields: Vec::with_capacity(self.capacity),
+            position: Position::default(),
+        };
+        Ok(self.read_record(&mut record)?.then_some(record))
+    }
+
+    fn read_fields(&mut self, record: &mut BorrowedRecord<'a>) -> Result<bool> {
+        record.fields.clear();
+        loop {
+            let input = &self.input[self.offset..];
+            let complete = match input.is_empty() {
+                true => self.core.finish_into(&mut self.spans)?,
+                false => {
+                    let (used, result) = self.core.feed_into(input, &mut self.spans);
+                    self.offset += used;
+                    result?
+                }
+            };
+            if complete {
+                self.borrow_fields(record)?;
+                self.capacity = record.len();
+                self.check_record_end(record)?;
+                return Ok(true);
+            }
+            if input.is_empty() {
+                return Ok(false);
+            }
+        }
+    }
+
+    // a field that is in the input as it is gets borrowed from it, only
+    // the ones the tokenizer changed are copied
+    fn borrow_fields(&self, record: &mut BorrowedRecord<'a>) -> Result<()> {
+        let input = self.input;
+        let spans = &self.spans;
+        record.position = spans.position;
+        for (i, span) in spans.fields.iter().enumerate() {
+            let bytes = match span.owned {
+                true => &spans.owned[span.range.clone()],
+                false => &input[span.range.clone()],
+            };
+            let invalid = |err: std::str::Utf8Error| CsvError::InvalidUtf8 {
+                field: i as u64 + 1,
+                position: position_of(input, spans.position, span, err.valid_up_to()),
+            };
+            let field = match span.owned {
+                true => Cow::Owned(std::str::from_utf8(bytes).map_err(invalid)?.to_string()),
+                false => Cow::Borrowed(
+                    match self.text.and_then(|text| text.get(span.range.clone())) {
+                        Some(text) => text,
+                        None => std::str::from_utf8(&input[span.range.clone()]).map_err(invalid)?,
+                    },
+                ),
+            };
+            record.fields.push(field);
+        }
+        Ok(())
+    }
+
+    fn check_record_end(&mut self, record: &mut BorrowedRecord<'a>) -> Result<()> {
+        let expected = *self.record_field_count.get_or_insert(record.len() as u64);
+        if record.len() as u64 != expected {
+            let irregular = CsvError::UnequalLengths {
+                expected,
+                found: record.len() as u64,
+                position: record.position,
+            };
+            let expected = expected as usize;
+            match &self.options.ragged {
+                RaggedRows::Error => return Err(irregular),
+                RaggedRows::Accept => {}
+                RaggedRows::Pad(value) => record
+                    .fields
+                    .resize(expected.max(record.len()), Cow::Owned(value.clone())),
+                RaggedRows::Truncate => record.fields.truncate(expected),
+                RaggedRows::PadAndTruncate(value) => {
+                    record.fields.resize(expected, Cow::Owned(value.clone()))
+                }
+            }
+            self.warnings.push(irregular);
+        }
+        Ok(())
+    }
+}
+
+// where the byte `valid` bytes into `span` is, counted from the start of the
+// record. in a field that was copied the escapes before it are not counted
+fn position_of(input: &[u8], record: Position, span: &Span, valid: usize) -> Position {
+    let at = match span.owned {
+        true => span.start + valid,
+        false => span.range.start + valid,
+    }
+    .min(input.len());
+    let from = (record.byte() as usize).min(at);
+    let bytes = &input[from..at];
+    let chars = |bytes: &[u8]| bytes.iter().filter(|b| **b as i8 >= -0x40).count() as u64;
+    let mut line = record.line();
+    let mut column = record.column();
+    let mut last = None;
+    for i in memchr::memchr2_iter(b'\r', b'\n', bytes) {
+        // "\r\n" is one line break
+        if !(bytes[i] == b'\n' && i > 0 && bytes[i - 1] == b'\r') {
+            line += 1;
+        }
+        last = Some(i);
+    }
+    match last {
+        Some(i) => column = 1 + chars(&bytes[i + 1..]),
+        None => column += chars(bytes),
+    }
+    Position::new(record.record(), line, column, at as u64)
+}
+
+impl<'a> Iterator for SliceParser<'a> {
+    type Item = Result<BorrowedRecord<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
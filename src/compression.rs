@@ -0,0 +1,145 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    pub fn for_label(label: &str) -> Option<Compression> {
+        match label.to_ascii_lowercase().as_str() {
+            "gzip" | "gz" => Some(Compression::Gzip),
+            "zstd" | "zst" => Some(Compression::Zstd),
+            "bzip2" | "bz2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+
+    // from the magic bytes every compressed stream starts with
+    pub fn detect(data: &[u8]) -> Option<Compression> {
+        match data {
+            [0x1F, 0x8B, ..] => Some(Compression::Gzip),
+            [0x28, 0xB5, 0x2F, 0xFD, ..] => Some(Compression::Zstd),
+            // the block size and then a block, or the end of an empty stream
+            [b'B', b'Z', b'h', b'1'..=b'9', rest @ ..]
+                if rest.starts_with(b"\x31\x41\x59\x26\x53\x59")
+                    || rest.starts_with(b"\x17\x72\x45\x38\x50\x90") =>
+            {
+                Some(Compression::Bzip2)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn from_path(file_path: &Path) -> Option<Compression> {
+        Compression::for_label(file_path.extension()?.to_str()?)
+    }
+}
+
+// the names are the cargo features too
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        })
+    }
+}
+
+enum Inner<R: Read> {
+    Plain(io::BufReader<R>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::bufread::MultiGzDecoder<io::BufReader<R>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, io::BufReader<R>>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::bufread::MultiBzDecoder<io::BufReader<R>>),
+}
+
+// a reader that undoes the compression of what it reads, each format needs
+// its cargo feature and input that is not compressed is passed through
+pub struct Decompress<R: Read> {
+    inner: Inner<R>,
+    compression: Option<Compression>,
+}
+
+impl<R: Read> Decompress<R> {
+    // looks at the first bytes of `reader` to find how it is compressed
+    pub fn new(reader: R) -> io::Result<Self> {
+        Self::detect(reader, None)
+    }
+
+    // `fallback` is used when the first bytes are not magic bytes, like the
+    // compression a file's extension asks for
+    pub(crate) fn detect(reader: R, fallback: Option<Compression>) -> io::Result<Self> {
+        let mut reader = io::BufReader::new(reader);
+        let compression = Compression::detect(reader.fill_buf()?).or(fallback);
+        Self::wrap(reader, compression)
+    }
+
+    pub fn with_compression(reader: R, compression: Option<Compression>) -> io::Result<Self> {
+        Self::wrap(io::BufReader::new(reader), compression)
+    }
+
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    fn wrap(reader: io::BufReader<R>, compression: Option<Compression>) -> io::Result<Self> {
+        let inner = match compression {
+            None => Inner::Plain(reader),
+            #[cfg(feature = "gzip")]
+            Some(Compression::Gzip) => Inner::Gzip(flate2::bufread::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => {
+                Inner::Zstd(zstd::stream::read::Decoder::with_buffer(reader)?)
+            }
+            #[cfg(feature = "bzip2")]
+            Some(Compression::Bzip2) => Inner::Bzip2(bzip2::bufread::MultiBzDecoder::new(reader)),
+            #[allow(unreachable_patterns)]
+            Some(compression) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("Reading {compression} input needs the {compression} feature"),
+                ))
+            }
+        };
+        Ok(Decompress { inner, compression })
+    }
+}
+
+impl Decompress<File> {
+    // a file as `CsvParser::from_path` reads it, found by its first bytes or
+    // else its extension
+    pub fn from_path(file_path: &Path) -> io::Result<Self> {
+        Self::detect(File::open(file_path)?, Compression::from_path(file_path))
+    }
+}
+
+impl<R: Read> Read for Decompress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(reader) => reader.read(buf),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(reader) => reader.read(buf),
+            #[cfg(feature = "bzip2")]
+            Inner::Bzip2(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read> fmt::Debug for Decompress<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decompress")
+            .field("compression", &self.compression)
+            .finish()
+    }
+}
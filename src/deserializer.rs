@@ -0,0 +1,694 @@
+use std::fmt;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::Deserialize;
+
+use crate::{CsvError, CsvParser, Record, Result};
+
+// the error serde sees while deserializing, `field` is filled in by the
+// record once it knows which field failed
+#[derive(Debug)]
+struct DeError {
+    field: Option<usize>,
+    message: String,
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        DeError {
+            field: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Record {
+    // fields are matched to struct fields by header name when the record has
+    // headers and by position otherwise
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T> {
+        self.deserialize_with(None)
+    }
+
+    fn deserialize_with<'de, T: Deserialize<'de>>(
+        &'de self,
+        separator: Option<&'de str>,
+    ) -> Result<T> {
+        let mut deserializer = RecordDeserializer {
+            record: self,
+            headers: self.headers(),
+            separator,
+            index: 0,
+        };
+        T::deserialize(&mut deserializer).map_err(|err| {
+            let header = err
+                .field
+                .and_then(|field| self.headers()?.get(field))
+                .cloned();
+            CsvError::Deserialize {
+                field: err.field.map(|field| field as u64 + 1),
+                header,
+                message: err.message,
+                position: *self.position(),
+            }
+        })
+    }
+}
+
+impl<R: Read> CsvParser<R> {
+    // headers like `address.city` fill the `city` field of a nested `address`
+    // struct, the same layout `CsvWriter::serialize` writes
+    pub fn header_separator(mut self, separator: &str) -> Self {
+        self.header_separator = Some(separator.to_string());
+        self
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&mut self) -> DeserializeRecords<'_, R, T> {
+        DeserializeRecords {
+            parser: self,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// a record that does not fit `T` is returned as an error and the next one
+// is still read, only parse errors end the iterator
+pub struct DeserializeRecords<'a, R: Read, T> {
+    parser: &'a mut CsvParser<R>,
+    _marker: PhantomData<T>,
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for DeserializeRecords<'_, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parser.next()? {
+            Ok(record) => Some(record.deserialize_with(self.parser.header_separator.as_deref())),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+struct RecordDeserializer<'de> {
+    record: &'de Record,
+    headers: Option<&'de [String]>,
+    separator: Option<&'de str>,
+    index: usize,
+}
+
+impl<'de> RecordDeserializer<'de> {
+    fn next_field(&mut self) -> std::result::Result<FieldDeserializer<'de>, DeError> {
+        let index = self.index;
+        match self.record.get(index) {
+            Some(value) => {
+                self.index += 1;
+                Ok(FieldDeserializer { value, index })
+            }
+            None => Err(de::Error::custom(format!(
+                "record has only {} fields",
+                self.record.len()
+            ))),
+        }
+    }
+
+    fn header_map(&self, headers: &'de [String]) -> HeaderMap<'de> {
+        let entries = headers
+            .iter()
+            .take(self.record.len())
+            .map(|header| header.as_str())
+            .enumerate()
+            .collect();
+        HeaderMap::new(self.record, entries, self.separator)
+    }
+}
+
+// a struct or map takes the whole record, anything else takes the next field
+macro_rules! forward_to_field {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+                self.next_field()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut RecordDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.headers {
+            Some(headers) => visitor.visit_map(self.header_map(headers)),
+            None => visitor.visit_seq(self),
+        }
+    }
+
+    forward_to_field! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.record.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.headers {
+            Some(headers) => visitor.visit_map(self.header_map(headers)),
+            None => Err(de::Error::custom(
+                "a map can only be read from a file with a header",
+            )),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.headers {
+            Some(headers) => visitor.visit_map(self.header_map(headers)),
+            None => visitor.visit_seq(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.next_field()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> SeqAccess<'de> for RecordDeserializer<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> std::result::Result<Option<T::Value>, DeError> {
+        if self.index >= self.record.len() {
+            return Ok(None);
+        }
+        let field = self.next_field()?;
+        let index = field.index;
+        seed.deserialize(field)
+            .map(Some)
+            .map_err(|err| at(err, index))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.record.len() - self.index)
+    }
+}
+
+// walks the header names of a record, with a separator a run of headers like
+// `address.city`, `address.zip` is handed to the `address` field as one struct
+struct HeaderMap<'de> {
+    record: &'de Record,
+    // the field index and what is left of its header name at this depth
+    entries: Vec<(usize, &'de str)>,
+    pos: usize,
+    separator: Option<&'de str>,
+    value: Range<usize>,
+    nested: bool,
+}
+
+impl<'de> HeaderMap<'de> {
+    fn new(
+        record: &'de Record,
+        entries: Vec<(usize, &'de str)>,
+        separator: Option<&'de str>,
+    ) -> Self {
+        HeaderMap {
+            record,
+            entries,
+            pos: 0,
+            separator,
+            value: 0..0,
+            nested: false,
+        }
+    }
+
+    fn prefix(&self, key: &'de str) -> Option<&'de str> {
+        let (prefix, _) = key.split_once(self.separator?)?;
+        Some(prefix)
+    }
+}
+
+impl<'de> MapAccess<'de> for HeaderMap<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> std::result::Result<Option<K::Value>, DeError> {
+        let (_, key) = match self.entries.get(self.pos) {
+            Some(entry) => *entry,
+            None => return Ok(None),
+        };
+        let start = self.pos;
+        let key = match self.prefix(key) {
+            Some(prefix) => {
+                self.pos += 1;
+                while self
+                    .entries
+                    .get(self.pos)
+                    .is_some_and(|(_, key)| self.prefix(key) == Some(prefix))
+                {
+                    self.pos += 1;
+                }
+                self.nested = true;
+                prefix
+            }
+            None => {
+                self.pos += 1;
+                self.nested = false;
+                key
+            }
+        };
+        self.value = start..self.pos;
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        let entries = &self.entries[self.value.clone()];
+        if !self.nested {
+            let (index, _) = entries[0];
+            let value = self.record.get(index).unwrap_or_default();
+            return seed
+                .deserialize(FieldDeserializer { value, index })
+                .map_err(|err| at(err, index));
+        }
+        let separator = self.separator.unwrap_or_default();
+        let entries = entries
+            .iter()
+            .map(|(index, key)| match key.split_once(separator) {
+                Some((_, rest)) => (*index, rest),
+                None => (*index, *key),
+            })
+            .collect();
+        seed.deserialize(GroupDeserializer(HeaderMap::new(
+            self.record,
+            entries,
+            self.separator,
+        )))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len() - self.pos)
+    }
+}
+
+// the value of a nested header group, it can only be read as a struct or map
+struct GroupDeserializer<'de>(HeaderMap<'de>);
+
+impl<'de> de::Deserializer<'de> for GroupDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_map(self.0)
+    }
+
+    // `None` when every field of the group is empty
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        let map = &self.0;
+        let empty = map
+            .entries
+            .iter()
+            .all(|(index, _)| map.record.get(*index).unwrap_or_default().is_empty());
+        match empty {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+fn at(mut err: DeError, index: usize) -> DeError {
+    if err.field.is_none() {
+        err.field = Some(index);
+    }
+    err
+}
+
+struct FieldDeserializer<'de> {
+    value: &'de str,
+    index: usize,
+}
+
+impl FieldDeserializer<'_> {
+    fn parse<T>(&self, ty: &str) -> std::result::Result<T, DeError>
+    where
+        T: std::str::FromStr,
+        T::Err: fmt::Display,
+    {
+        self.value.parse().map_err(|err| DeError {
+            field: Some(self.index),
+            message: format!("cannot parse {:?} as {ty}: {err}", self.value),
+        })
+    }
+}
+
+macro_rules! parse_field {
+    ($($method:ident $visit:ident $ty:ty)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FieldDeserializer<'de> {
+    type Error = DeError;
+
+    // without a type to go by (flatten, untagged enums) a field is the first
+    // of bool, integer or float that it parses as and a string otherwise
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        if let Ok(value) = self.value.parse::<bool>() {
+            visitor.visit_bool(value)
+        } else if let Ok(value) = self.value.parse::<u64>() {
+            visitor.visit_u64(value)
+        } else if let Ok(value) = self.value.parse::<i64>() {
+            visitor.visit_i64(value)
+        } else if let Ok(value) = self.value.parse::<f64>() {
+            visitor.visit_f64(value)
+        } else {
+            visitor.visit_borrowed_str(self.value)
+        }
+    }
+
+    parse_field! {
+        deserialize_bool visit_bool bool
+        deserialize_i8 visit_i8 i8
+        deserialize_i16 visit_i16 i16
+        deserialize_i32 visit_i32 i32
+        deserialize_i64 visit_i64 i64
+        deserialize_i128 visit_i128 i128
+        deserialize_u8 visit_u8 u8
+        deserialize_u16 visit_u16 u16
+        deserialize_u32 visit_u32 u32
+        deserialize_u64 visit_u64 u64
+        deserialize_u128 visit_u128 u128
+        deserialize_f32 visit_f32 f32
+        deserialize_f64 visit_f64 f64
+        deserialize_char visit_char char
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_bytes(self.value.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_bytes(self.value.as_bytes())
+    }
+
+    // an empty field is `None`
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.value.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.value.is_empty() {
+            true => visitor.visit_unit(),
+            false => Err(de::Error::invalid_type(
+                de::Unexpected::Str(self.value),
+                &"an empty field",
+            )),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        Err(de::Error::custom("a single field cannot hold a sequence"))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        Err(de::Error::custom("a single field cannot hold a map"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    // only unit variants fit in a field, the field holds the variant name
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> EnumAccess<'de> for FieldDeserializer<'de> {
+    type Error = DeError;
+    type Variant = Self;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> std::result::Result<(T::Value, Self), DeError> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::new(self.value))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for FieldDeserializer<'de> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> std::result::Result<(), DeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        _seed: T,
+    ) -> std::result::Result<T::Value, DeError> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"a unit variant",
+        ))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::TupleVariant,
+            &"a unit variant",
+        ))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::StructVariant,
+            &"a unit variant",
+        ))
+    }
+}
@@ -0,0 +1,120 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    // any of "\r\n", "\r" or "\n"
+    CRLF,
+    Any(u8),
+}
+
+// how a quote gets inside a quoted field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    // RFC 4180 `""`, and the byte after `escape` when one is set
+    Doubled,
+    // `\"`, `\\`, `\n` and the like in any field, the way MySQL and unix
+    // tools write them. `escape` takes the place of the backslash when one is set
+    Backslash,
+    // a quote always closes the field
+    None,
+}
+
+impl Escape {
+    pub fn for_label(label: &str) -> Option<Escape> {
+        match label.to_ascii_lowercase().as_str() {
+            "doubled" | "double" => Some(Escape::Doubled),
+            "backslash" => Some(Escape::Backslash),
+            "none" => Some(Escape::None),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    pub delimiter: u8,
+    pub quote: u8,
+    pub escape: Option<u8>,
+    pub escaping: Escape,
+    // when false the quote is a plain byte
+    pub quoting: bool,
+    pub terminator: Terminator,
+}
+
+impl Default for Dialect {
+    // RFC 4180
+    fn default() -> Self {
+        Dialect {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            escaping: Escape::Doubled,
+            quoting: true,
+            terminator: Terminator::CRLF,
+        }
+    }
+}
+
+impl Dialect {
+    pub fn new() -> Self {
+        Dialect::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn escape(mut self, escape: Option<u8>) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    pub fn escaping(mut self, escaping: Escape) -> Self {
+        self.escaping = escaping;
+        self
+    }
+
+    pub fn quoting(mut self, flag: bool) -> Self {
+        self.quoting = flag;
+        self
+    }
+
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    pub(crate) fn quote_byte(&self) -> Option<u8> {
+        self.quoting.then_some(self.quote)
+    }
+
+    // the byte that takes the one after it as data, inside quotes only
+    // unless the escaping is `Backslash`
+    pub(crate) fn escape_byte(&self) -> Option<u8> {
+        let escape = self
+            .escape
+            .filter(|escape| Some(*escape) != self.quote_byte());
+        match self.escaping {
+            Escape::Doubled => escape,
+            Escape::Backslash => Some(escape.unwrap_or(b'\\')),
+            Escape::None => None,
+        }
+    }
+}
+
+// what a backslash escape stands for, MySQL's list
+pub(crate) fn unescape(b: u8) -> u8 {
+    match b {
+        b'0' => b'\0',
+        b'b' => 0x08,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'Z' => 0x1A,
+        b => b,
+    }
+}
@@ -0,0 +1,285 @@
+use std::fmt;
+use std::io::{self, Read};
+
+use crate::{CsvError, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        match label.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" | "utf16le" | "utf-16" | "utf16" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            "iso-8859-1" | "latin1" | "latin-1" => Some(Encoding::Latin1),
+            "windows-1252" | "cp1252" => Some(Encoding::Windows1252),
+            _ => None,
+        }
+    }
+
+    fn bom(data: &[u8]) -> Option<(Encoding, usize)> {
+        match data {
+            [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+            [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+            _ => None,
+        }
+    }
+
+    // text without a BOM that has a NUL in every other byte is UTF-16. one
+    // unit is not enough to tell, a record of a lone NUL looks like one
+    fn guess_utf16(data: &[u8]) -> Option<Encoding> {
+        let sample = &data[..data.len().min(64) & !1];
+        if sample.len() < 4 {
+            return None;
+        }
+        let units = sample.len() / 2;
+        let even = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let odd = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count();
+        if odd * 2 > units && even == 0 {
+            Some(Encoding::Utf16Le)
+        } else if even * 2 > units && odd == 0 {
+            Some(Encoding::Utf16Be)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Windows1252 => "windows-1252",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// windows-1252 differs from latin-1 only in 0x80..=0x9F, the unused
+// slots map to the matching C1 control like browsers do
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+// turns the input into UTF-8 before the parser sees it, a BOM is always
+// dropped and when no encoding is given the BOM picks one
+#[derive(Debug)]
+pub(crate) struct Decoder<R: Read> {
+    inner: R,
+    encoding: Option<Encoding>,
+    started: bool,
+    eof: bool,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    out_pos: usize,
+    // bytes of the original input that were decoded so far
+    offset: u64,
+}
+
+impl<R: Read> Decoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Decoder {
+            inner,
+            encoding: None,
+            started: false,
+            eof: false,
+            input: Vec::new(),
+            output: Vec::new(),
+            out_pos: 0,
+            offset: 0,
+        }
+    }
+
+    // for UTF-8 input that starts part way into a file, where nothing can be a BOM
+    pub(crate) fn utf8_tail(inner: R) -> Self {
+        Decoder {
+            encoding: Some(Encoding::Utf8),
+            started: true,
+            ..Decoder::new(inner)
+        }
+    }
+
+    pub(crate) fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = Some(encoding);
+    }
+
+    pub(crate) fn encoding(&self) -> Option<Encoding> {
+        match self.started {
+            true => self.encoding,
+            false => None,
+        }
+    }
+
+    fn fail(&self, error: CsvError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+
+    fn read_input(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; 8 * 1024];
+        let n = self.inner.read(&mut buf)?;
+        if n == 0 {
+            self.eof = true;
+        }
+        self.input.extend_from_slice(&buf[..n]);
+        Ok(())
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        while self.input.len() < 64 && !self.eof {
+            self.read_input()?;
+        }
+        self.started = true;
+        let position = Position::new(0, 1, 1, 0);
+        match (Encoding::bom(&self.input), self.encoding) {
+            (Some((found, len)), None) => {
+                self.encoding = Some(found);
+                self.skip(len);
+            }
+            (Some((found, len)), Some(expected)) if found == expected => self.skip(len),
+            (Some((found, _)), Some(expected)) => {
+                return Err(self.fail(CsvError::EncodingMismatch {
+                    expected,
+                    found,
+                    position,
+                }))
+            }
+            (None, None) => {
+                if let Some(found) = Encoding::guess_utf16(&self.input) {
+                    return Err(self.fail(CsvError::EncodingMismatch {
+                        expected: Encoding::Utf8,
+                        found,
+                        position,
+                    }));
+                }
+                self.encoding = Some(Encoding::Utf8);
+            }
+            (None, Some(_)) => {}
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.input.drain(..len);
+        self.offset += len as u64;
+    }
+
+    fn decode(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.out_pos = 0;
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+        let used = match encoding {
+            Encoding::Utf8 => {
+                std::mem::swap(&mut self.input, &mut self.output);
+                self.output.len()
+            }
+            Encoding::Latin1 | Encoding::Windows1252 => {
+                for b in self.input.iter() {
+                    let c = match (encoding, b) {
+                        (Encoding::Windows1252, 0x80..=0x9F) => WINDOWS_1252[*b as usize - 0x80],
+                        _ => *b as char,
+                    };
+                    let mut buf = [0u8; 4];
+                    self.output
+                        .extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                self.input.len()
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => self.decode_utf16(encoding)?,
+        };
+        self.input.drain(..used.min(self.input.len()));
+        self.offset += used as u64;
+        if self.eof && !self.input.is_empty() {
+            return Err(self.fail(CsvError::InvalidEncoding {
+                encoding,
+                position: Position::from_byte(self.offset),
+            }));
+        }
+        Ok(())
+    }
+
+    // returns how many input bytes were used, a split code unit or surrogate
+    // pair at the end is left for the next read
+    fn decode_utf16(&mut self, encoding: Encoding) -> io::Result<usize> {
+        let unit = |pair: &[u8]| match encoding {
+            Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+            _ => u16::from_le_bytes([pair[0], pair[1]]),
+        };
+        let mut i = 0;
+        while i + 2 <= self.input.len() {
+            let first = unit(&self.input[i..i + 2]);
+            let (c, len) = match first {
+                0xD800..=0xDBFF => {
+                    if i + 4 > self.input.len() {
+                        break;
+                    }
+                    let second = unit(&self.input[i + 2..i + 4]);
+                    let c = char::decode_utf16([first, second])
+                        .next()
+                        .and_then(|c| c.ok());
+                    (c, 4)
+                }
+                _ => (char::from_u32(first as u32), 2),
+            };
+            let c = match c {
+                Some(c) => c,
+                None => {
+                    return Err(self.fail(CsvError::InvalidEncoding {
+                        encoding,
+                        position: Position::from_byte(self.offset + i as u64),
+                    }))
+                }
+            };
+            let mut buf = [0u8; 4];
+            self.output
+                .extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            i += len;
+        }
+        Ok(i)
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.started {
+            self.start()?;
+        }
+        while self.out_pos == self.output.len() {
+            if self.input.is_empty() && self.encoding == Some(Encoding::Utf8) {
+                // nothing to convert, let the caller read straight from the input
+                let n = self.inner.read(buf)?;
+                self.offset += n as u64;
+                return Ok(n);
+            }
+            if self.input.is_empty() && self.eof {
+                return Ok(0);
+            }
+            if !self.eof {
+                self.read_input()?;
+            }
+            self.decode()?;
+        }
+        let n = buf.len().min(self.output.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.output[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
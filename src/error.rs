@@ -0,0 +1,212 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::{Encoding, Position};
+
+pub type Result<T> = std::result::Result<T, CsvError>;
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io {
+        error: io::Error,
+        position: Position,
+    },
+    InvalidUtf8 {
+        field: u64,
+        position: Position,
+    },
+    UnexpectedQuote {
+        position: Position,
+    },
+    UnterminatedQuote {
+        position: Position,
+    },
+    UnequalLengths {
+        expected: u64,
+        found: u64,
+        position: Position,
+    },
+    EmptyHeader {
+        column: u64,
+        position: Position,
+    },
+    DuplicateHeader {
+        name: String,
+        first: u64,
+        second: u64,
+        position: Position,
+    },
+    TooManyErrors {
+        max: u64,
+        position: Position,
+    },
+    EncodingMismatch {
+        expected: Encoding,
+        found: Encoding,
+        position: Position,
+    },
+    InvalidEncoding {
+        encoding: Encoding,
+        position: Position,
+    },
+    Deserialize {
+        field: Option<u64>,
+        header: Option<String>,
+        message: String,
+        position: Position,
+    },
+    Serialize {
+        field: Option<String>,
+        message: String,
+        position: Position,
+    },
+    UnwritableQuote {
+        field: u64,
+        position: Position,
+    },
+}
+
+impl CsvError {
+    pub fn position(&self) -> &Position {
+        match self {
+            CsvError::Io { position, .. } => position,
+            CsvError::InvalidUtf8 { position, .. } => position,
+            CsvError::UnexpectedQuote { position } => position,
+            CsvError::UnterminatedQuote { position } => position,
+            CsvError::UnequalLengths { position, .. } => position,
+            CsvError::EmptyHeader { position, .. } => position,
+            CsvError::DuplicateHeader { position, .. } => position,
+            CsvError::TooManyErrors { position, .. } => position,
+            CsvError::EncodingMismatch { position, .. } => position,
+            CsvError::InvalidEncoding { position, .. } => position,
+            CsvError::Deserialize { position, .. } => position,
+            CsvError::Serialize { position, .. } => position,
+            CsvError::UnwritableQuote { position, .. } => position,
+        }
+    }
+
+    // the decoder can only report through io::Error, so its errors come back wrapped
+    pub(crate) fn from_io(error: io::Error, position: Position) -> CsvError {
+        if !error.get_ref().is_some_and(|inner| inner.is::<CsvError>()) {
+            return CsvError::Io { error, position };
+        }
+        let kind = error.kind();
+        match error.into_inner().map(|inner| inner.downcast::<CsvError>()) {
+            Some(Ok(inner)) => *inner,
+            _ => CsvError::Io {
+                error: io::Error::from(kind),
+                position,
+            },
+        }
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io { error, position } => write!(f, "at {position}: {error}"),
+            CsvError::InvalidUtf8 { field, position } => {
+                write!(f, "at {position}: invalid UTF-8 in field {field}")
+            }
+            CsvError::UnexpectedQuote { position } => {
+                write!(f, "at {position}: unexpected quote")
+            }
+            CsvError::UnterminatedQuote { position } => {
+                write!(f, "at {position}: unterminated quoted field")
+            }
+            CsvError::UnequalLengths {
+                expected,
+                found,
+                position,
+            } => write!(
+                f,
+                "at {position}: expect file to have {expected} fields found {found}"
+            ),
+            CsvError::EmptyHeader { column, position } => {
+                write!(f, "at {position}: header in column {column} is empty")
+            }
+            CsvError::DuplicateHeader {
+                name,
+                first,
+                second,
+                position,
+            } => write!(
+                f,
+                "at {position}: duplicate header {name:?} in columns {first} and {second}"
+            ),
+            CsvError::TooManyErrors { max, position } => {
+                write!(f, "at {position}: more than {max} bad rows")
+            }
+            CsvError::EncodingMismatch {
+                expected,
+                found,
+                position,
+            } => write!(
+                f,
+                "at {position}: expected {expected} input but it looks like {found}"
+            ),
+            // the decoder only knows the byte offset in the input as read
+            CsvError::InvalidEncoding { encoding, position } => {
+                write!(f, "at byte {}: invalid {encoding}", position.byte())
+            }
+            CsvError::Deserialize {
+                field,
+                header,
+                message,
+                position,
+            } => match (field, header) {
+                (Some(field), Some(header)) => {
+                    write!(f, "at {position}: field {field} ({header:?}): {message}")
+                }
+                (Some(field), None) => write!(f, "at {position}: field {field}: {message}"),
+                _ => write!(f, "at {position}: {message}"),
+            },
+            // the writer only knows which record it is on
+            CsvError::Serialize {
+                field,
+                message,
+                position,
+            } => match field {
+                Some(field) => write!(
+                    f,
+                    "at record {}: field {field:?}: {message}",
+                    position.record()
+                ),
+                None => write!(f, "at record {}: {message}", position.record()),
+            },
+            CsvError::UnwritableQuote { field, position } => write!(
+                f,
+                "at record {}: field {field} has a quote the dialect cannot escape",
+                position.record()
+            ),
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> Self {
+        CsvError::Io {
+            error,
+            position: Position::default(),
+        }
+    }
+}
+
+impl From<CsvError> for io::Error {
+    fn from(error: CsvError) -> Self {
+        match error {
+            CsvError::Io { error, .. } => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
@@ -20,404 +20,538 @@
 * */
 #![allow(warnings)] // At the top of the file
 use std::fs::File;
-use std::io::{self, Read};
-use std::path::PathBuf;
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// first, the parsers use its macro
+#[macro_use]
+mod options;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod borrowed;
+mod compression;
+#[cfg(feature = "serde")]
+mod deserializer;
+mod dialect;
+mod encoding;
+mod error;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod parallel;
+mod push;
+mod record;
+#[cfg(feature = "serde")]
+mod serializer;
+mod sniffer;
+mod tokenizer;
+mod writer;
+
+use encoding::Decoder;
+
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncCsvParser;
+pub use borrowed::{BorrowedRecord, SliceParser};
+pub use compression::{Compression, Decompress};
+#[cfg(feature = "serde")]
+pub use deserializer::DeserializeRecords;
+pub use dialect::{Dialect, Escape, Terminator};
+pub use encoding::Encoding;
+pub use error::{CsvError, Result};
+#[cfg(feature = "mmap")]
+pub use mmap::MappedFile;
+pub use options::Options;
+pub use parallel::ParallelParser;
+pub use push::PushParser;
+pub use record::{ByteRecord, ByteRecordIter, FieldIndex, Position, Record, RecordIter, Utf8Mode};
+pub use sniffer::{Sniffed, Sniffer};
+pub use tokenizer::CsvCore;
+pub use writer::{CsvWriter, QuoteStyle};
+
+pub type Token = Record;
+
+// what to do with a record whose field count differs from the first record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaggedRows {
+    Error,
+    Accept,
+    Pad(String),
+    Truncate,
+    PadAndTruncate(String),
+}
+
+#[derive(Debug)]
+pub struct BadRow {
+    pub raw: String,
+    pub error: CsvError,
+}
 
-#[derive(Debug, Clone)]
-enum TokenType {
-    Record,
-    Eof,
+fn pad_record(record: &mut ByteRecord, len: usize, value: &str) {
+    while record.len() < len {
+        record.push_field(value.as_bytes());
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Token {
-    token_type: TokenType,
-    value: Vec<String>,
-    line: u64,
+fn check_headers<T: AsRef<str>>(names: &[T], position: Position) -> Result<()> {
+    for (i, name) in names.iter().enumerate() {
+        let name = name.as_ref();
+        if name.is_empty() {
+            return Err(CsvError::EmptyHeader {
+                column: i as u64 + 1,
+                position,
+            });
+        }
+        if let Some(first) = names[..i].iter().position(|other| other.as_ref() == name) {
+            return Err(CsvError::DuplicateHeader {
+                name: name.to_string(),
+                first: first as u64 + 1,
+                second: i as u64 + 1,
+                position,
+            });
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug)]
 pub struct CsvParser<R: Read> {
-    tokens: Vec<Token>,
-    reader: io::BufReader<R>,
-    record_field_count: u64,
-    line: u64,
-    peek: Option<char>,
-    has_header: bool,
-    trim_space: bool,
-    all_whitespace_empty: bool,
+    reader: io::BufReader<Decoder<R>>,
+    options: Options,
+    core: CsvCore,
+    capacity: (usize, usize),
+    record_field_count: Option<u64>,
+    headers: Option<Arc<Vec<String>>>,
+    #[cfg(feature = "serde")]
+    header_separator: Option<String>,
+    utf8: Utf8Mode,
+    warnings: Vec<CsvError>,
+    recover: bool,
+    max_errors: Option<u64>,
+    error_count: u64,
+    raw: Vec<u8>,
+    quarantine: Vec<BadRow>,
+    done: bool,
 }
 
 impl<R: Read> CsvParser<R> {
     pub fn new(reader: R) -> Self {
-        let mut ret = CsvParser {
-            tokens: Vec::<Token>::new(),
-            reader: io::BufReader::new(reader),
-            record_field_count: 0,
-            line: 1,
-            peek: None,
-            has_header: true,
-            trim_space: false,
-            all_whitespace_empty: false,
-        };
-        ret.next_char();
-        ret
+        CsvParser {
+            reader: io::BufReader::with_capacity(64 * 1024, Decoder::new(reader)),
+            options: Options::default(),
+            core: CsvCore::new(Dialect::default()),
+            capacity: (0, 0),
+            record_field_count: None,
+            headers: None,
+            #[cfg(feature = "serde")]
+            header_separator: None,
+            utf8: Utf8Mode::Strict,
+            warnings: Vec::new(),
+            recover: false,
+            max_errors: None,
+            error_count: 0,
+            raw: Vec::new(),
+            quarantine: Vec::new(),
+            done: false,
+        }
     }
 
-    pub fn with_header(mut self, flag: bool) -> Self {
-        self.has_header = flag;
+    options_setters!();
+
+    pub(crate) fn set_options(mut self, f: impl FnOnce(Options) -> Options) -> Self {
+        self.options = f(self.options);
+        self.core = self.options.core();
         self
     }
 
-    pub fn trim_space(mut self, flag: bool) -> Self {
-        self.trim_space = flag;
+    // the input is converted to UTF-8 from this encoding, without it a BOM decides
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.reader.get_mut().set_encoding(encoding);
         self
     }
 
-    pub fn all_whitespace_empty(mut self, flag: bool) -> Self {
-        self.all_whitespace_empty = flag;
+    // the encoding that was given or detected, known once reading started
+    pub fn input_encoding(&self) -> Option<Encoding> {
+        self.reader.get_ref().encoding()
+    }
+
+    // the skipped and comment lines before the header or first record, read
+    // together with it
+    pub fn preamble(&self) -> &[String] {
+        self.core.preamble()
+    }
+
+    // how records are turned into text, byte_records() skips this step
+    pub fn utf8(mut self, mode: Utf8Mode) -> Self {
+        self.utf8 = mode;
         self
     }
 
-    fn next_char_(&mut self) -> io::Result<Option<char>> {
-        let mut buf = [0u8; 4];
-        let mut len = 0;
+    // every irregular record that was let through by the ragged rows policy
+    pub fn warnings(&self) -> &[CsvError] {
+        &self.warnings
+    }
 
-        match self.reader.read(&mut buf[0..1]) {
-            Ok(0) => return Ok(None),
-            Ok(_) => len += 1,
-            Err(e) => return Err(e),
-        }
+    pub fn recover(mut self, flag: bool) -> Self {
+        self.recover = flag;
+        self
+    }
 
-        let utf8_len = match buf[0] {
-            0x00..=0x7F => 1,
-            0xC0..=0xDF => 2,
-            0xE0..=0xEF => 3,
-            0xF0..=0xF7 => 4,
-            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid UTF-8")),
-        };
+    pub fn max_errors(mut self, max: u64) -> Self {
+        self.max_errors = Some(max);
+        self
+    }
 
-        while len < utf8_len {
-            match self.reader.read(&mut buf[len..len + 1]) {
-                Ok(0) => break,
-                Ok(_) => len += 1,
-                Err(e) => return Err(e),
+    // rows rejected while recovering, in the order they were found
+    pub fn quarantine(&self) -> &[BadRow] {
+        &self.quarantine
+    }
+
+    pub fn take_quarantine(&mut self) -> Vec<BadRow> {
+        std::mem::take(&mut self.quarantine)
+    }
+
+    fn check_record_end(&mut self, record: &mut ByteRecord) -> Result<()> {
+        let expected = match self.record_field_count {
+            Some(expected) => expected,
+            None => {
+                self.record_field_count = Some(record.len() as u64);
+                record.len() as u64
             }
+        };
+        if record.len() as u64 != expected {
+            let irregular = CsvError::UnequalLengths {
+                expected,
+                found: record.len() as u64,
+                position: *record.position(),
+            };
+            let expected = expected as usize;
+            match &self.options.ragged {
+                RaggedRows::Error => return Err(irregular),
+                RaggedRows::Accept => {}
+                RaggedRows::Pad(value) => pad_record(record, expected, value),
+                RaggedRows::Truncate => record.truncate(expected),
+                RaggedRows::PadAndTruncate(value) => {
+                    pad_record(record, expected, value);
+                    record.truncate(expected);
+                }
+            }
+            self.warnings.push(irregular);
         }
+        Ok(())
+    }
 
-        match std::str::from_utf8(&buf[0..len]) {
-            Ok(s) => Ok(s.chars().next()),
-            Err(_) => Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid UTF-8")),
+    fn to_record(&self, record: ByteRecord) -> Result<Record> {
+        match self.utf8 {
+            Utf8Mode::Strict => Record::from_byte_record(record),
+            Utf8Mode::Lossy => Ok(Record::from_byte_record_lossy(record)),
         }
     }
 
-    fn next_char(&mut self) -> io::Result<Option<char>> {
-        let ret = self.peek;
-        match self.next_char_() {
-            Ok(c) => self.peek = c,
-            Err(err) => return Err(err),
+    fn read_headers(&mut self) -> Result<()> {
+        if !self.options.has_header || self.headers.is_some() {
+            return Ok(());
         }
+        let token = self.read_token()?;
+        self.set_header_row(token)
+    }
 
-        return Ok(ret);
-    }
-
-    fn check_record_end(&mut self, record: &mut Vec<String>) -> io::Result<()> {
-        if self.line == 1 {
-            self.record_field_count = record.len() as u64;
-        } else if record.len() as u64 != self.record_field_count {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "at line {:?} expect file to have {:?} fields found {:?}",
-                    self.line,
-                    self.record_field_count,
-                    record.len()
-                ),
-            ));
-        }
-        self.tokens.push(Token {
-            token_type: TokenType::Record,
-            value: record.clone(),
-            line: self.line,
-        });
-        self.line += 1;
-        return Ok(());
-    }
-
-    fn match_char(&mut self, c: io::Result<Option<char>>, curr: &mut String) -> io::Result<bool> {
-        match c {
-            Ok(Some(c)) => match c {
-                ',' => {
-                    if self.all_whitespace_empty && curr.trim().is_empty() {
-                        *curr = String::from("");
-                    } else {
-                        if self.trim_space {
-                            *curr = curr.trim().to_owned().clone();
-                        } else {
-                            *curr = curr.clone();
-                        }
-                    }
-                    return Ok(true);
-                }
-                '"' => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("at line {:?} found invalid char {c}", self.line),
-                    ));
-                }
-                '\r' | '\n' => {
-                    if self.all_whitespace_empty && curr.trim().is_empty() {
-                        *curr = String::from("");
-                    } else {
-                        if self.trim_space {
-                            *curr = curr.trim().to_owned().clone();
-                        } else {
-                            *curr = curr.clone();
-                        }
-                    }
-                    return Ok(true);
-                }
-                _ => {
-                    curr.push(c);
-                    return Ok(false);
-                }
-            },
-            Ok(None) => {
-                return Ok(true);
+    // the first record of the input, `None` when there is none
+    fn set_header_row(&mut self, token: Option<ByteRecord>) -> Result<()> {
+        let token = match token {
+            Some(token) => self.to_record(token)?,
+            None => {
+                self.headers = Some(Arc::new(Vec::new()));
+                return Ok(());
             }
-            Err(err) => return Err(err),
-        }
+        };
+        let position = *token.position();
+        let names = Vec::<String>::from(token);
+        check_headers(&names, position)?;
+        self.headers = Some(Arc::new(names));
+        Ok(())
     }
 
-    fn parse_escaped(&mut self) -> io::Result<(String, bool)> {
-        let mut curr = String::new();
-        let mut count = 1;
+    pub fn headers(&mut self) -> Result<Option<&[String]>> {
+        self.read_headers()?;
+        Ok(self.headers.as_ref().map(|headers| headers.as_slice()))
+    }
 
-        loop {
-            match self.next_char() {
-                Ok(Some(c)) => {
-                    match c {
-                        '"' => {
-                            count += 1;
-                            match self.peek {
-                                Some(peek) => match peek {
-                                    '"' => {
-                                        count += 1;
-                                        curr.push('"');
-                                        self.next_char();
-                                    }
-                                    ',' => {
-                                        if count % 2 != 0 {
-                                            return Err(io::Error::new(
-                                                io::ErrorKind::InvalidData,
-                                                format!(
-                                                    "at line {:?} unterninated escaped \"",
-                                                    self.line
-                                                ),
-                                            ));
-                                        }
-                                        self.next_char();
-                                        return Ok((curr, false));
-                                    }
-                                    '\r' | '\n' => {
-                                        if count % 2 != 0 {
-                                            return Err(io::Error::new(
-                                                io::ErrorKind::InvalidData,
-                                                format!(
-                                                    "at line {:?} unterninated escaped \"",
-                                                    self.line
-                                                ),
-                                            ));
-                                        }
-                                        return Ok((curr, true));
-                                    }
-                                    _ => {
-                                        return Err(io::Error::new(
-                                            io::ErrorKind::InvalidData,
-                                            format!(
-                                                "at line {:?} unterninated escaped \"",
-                                                self.line
-                                            ),
-                                        ));
-                                    }
-                                },
-                                None => {
-                                    if count % 2 != 0 {
-                                        return Err(io::Error::new(
-                                            io::ErrorKind::InvalidData,
-                                            format!(
-                                                "at line {:?} unterninated escaped \"",
-                                                self.line
-                                            ),
-                                        ));
-                                    }
-                                    return Ok((curr, true));
-                                }
-                            }
-                        }
-                        _ => {
-                            curr.push(c);
-                        }
-                    };
-                }
-                Ok(None) => return Ok((curr, true)),
-                Err(err) => return Err(err),
-            }
+    // like `next` but the fields are read into `record`, its buffers are
+    // reused so a loop over a whole file does not allocate per record.
+    // returns false once there are no records left
+    pub fn read_record(&mut self, record: &mut Record) -> Result<bool> {
+        let mut bytes = ByteRecord::from(std::mem::take(record));
+        let read = self.read_with(&mut bytes, |parser, bytes| {
+            *record = parser.to_record(std::mem::take(bytes))?;
+            record.set_headers(parser.headers.clone());
+            Ok(())
+        });
+        if !matches!(read, Ok(true)) {
+            // hand the buffers back for the next call
+            bytes.clear();
+            *record = Record::from_byte_record(bytes).unwrap_or_default();
         }
+        read
+    }
 
-        Ok((curr, true))
+    pub fn read_byte_record(&mut self, record: &mut ByteRecord) -> Result<bool> {
+        self.read_with(record, |_, _| Ok(()))
     }
 
-    fn parse_field(&mut self, first: char) -> io::Result<(String, Option<char>)> {
-        let mut curr = String::new();
-        let finshed = self.match_char(Ok(Some(first)), &mut curr)?;
-        if finshed {
-            return Ok((curr, Some(first)));
+    fn read_with<F>(&mut self, record: &mut ByteRecord, mut check: F) -> Result<bool>
+    where
+        F: FnMut(&Self, &mut ByteRecord) -> Result<()>,
+    {
+        if self.done {
+            record.clear();
+            return Ok(false);
         }
-        loop {
-            let c = self.next_char();
-            let ret_c = match c {
-                Ok(c) => c,
-                Err(err) => return Err(err),
+        let read = self.read_headers().and_then(|_| loop {
+            self.raw.clear();
+            let error = match self.read_token_into(record) {
+                Ok(true) => match check(self, record) {
+                    Ok(()) => return Ok(true),
+                    Err(error) => error,
+                },
+                Ok(false) => return Ok(false),
+                Err(error) => error,
             };
-            let finshed = self.match_char(c, &mut curr)?;
-            if finshed {
-                return Ok((curr, ret_c));
+            if !self.recover {
+                return Err(error);
             }
+            self.quarantine_row(error)?;
+        });
+        if !matches!(read, Ok(true)) {
+            self.done = true;
         }
+        read
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        // sized like the last record so it is allocated once
+        let (bytes, fields) = self.capacity;
+        let mut record = Record::with_capacity(bytes, fields);
+        Ok(self.read_record(&mut record)?.then_some(record))
     }
 
-    fn parse_record(&mut self, first: char) -> io::Result<()> {
-        let mut record = Vec::<String>::new();
-        match first {
-            '\n' | '\r' => {
-                self.check_record_end(&mut record);
+    fn next_byte_record(&mut self) -> Result<Option<ByteRecord>> {
+        let (bytes, fields) = self.capacity;
+        let mut record = ByteRecord::with_capacity(bytes, fields);
+        Ok(self.read_byte_record(&mut record)?.then_some(record))
+    }
+
+    pub fn byte_records(&mut self) -> ByteRecords<'_, R> {
+        ByteRecords { parser: self }
+    }
+
+    fn quarantine_row(&mut self, error: CsvError) -> Result<()> {
+        match error {
+            // the record was read in full and already counted
+            CsvError::InvalidUtf8 { .. } | CsvError::UnequalLengths { .. } => {}
+            CsvError::UnexpectedQuote { .. } | CsvError::UnterminatedQuote { .. } => {
+                self.skip_record()?;
             }
-            '"' => match self.parse_escaped() {
-                Ok(res) => {
-                    record.push(res.0);
-                    if res.1 {
-                        return self.check_record_end(&mut record);
-                    }
-                }
-                Err(err) => return Err(err),
-            },
-            _ => match self.parse_field(first) {
-                Ok(res) => {
-                    record.push(res.0);
-                    match res.1 {
-                        Some(c) => match c {
-                            ',' => match self.peek {
-                                Some(peek) => {
-                                    if peek == '\r' || peek == '\n' {
-                                        record.push(String::from(""));
-                                        return self.check_record_end(&mut record);
-                                    }
-                                }
-                                None => {
-                                    record.push(String::from(""));
-                                    return self.check_record_end(&mut record);
-                                }
-                            },
-                            '\r' | '\n' => {
-                                return self.check_record_end(&mut record);
-                            }
-                            _ => {}
-                        },
-                        None => {
-                            return self.check_record_end(&mut record);
-                        }
-                    }
-                }
-                Err(err) => return Err(err),
-            },
+            error => return Err(error),
         }
-        loop {
-            match self.next_char() {
-                Ok(Some(c)) => match c {
-                    '\n' | '\r' => {
-                        self.check_record_end(&mut record);
-                    }
-                    '"' => match self.parse_escaped() {
-                        Ok(res) => {
-                            record.push(res.0);
-                            if res.1 {
-                                return self.check_record_end(&mut record);
-                            }
-                        }
-                        Err(err) => return Err(err),
-                    },
-                    _ => match self.parse_field(c) {
-                        Ok(res) => {
-                            record.push(res.0);
-                            match res.1 {
-                                Some(c) => match c {
-                                    ',' => match self.peek {
-                                        Some(peek) => {
-                                            if peek == '\r' || peek == '\n' {
-                                                record.push(String::from(""));
-                                                return self.check_record_end(&mut record);
-                                            }
-                                        }
-                                        None => {
-                                            record.push(String::from(""));
-                                            return self.check_record_end(&mut record);
-                                        }
-                                    },
-                                    '\r' | '\n' => {
-                                        return self.check_record_end(&mut record);
-                                    }
-                                    _ => {}
-                                },
-                                None => {
-                                    return self.check_record_end(&mut record);
-                                }
-                            }
-                        }
-                        Err(err) => return Err(err),
-                    },
-                },
-                Ok(None) => return Ok(()),
-                Err(err) => return Err(err),
+        let raw = std::mem::take(&mut self.raw);
+        // the lines skipped before the record were read along with it
+        let fed = self.core.position().byte() - raw.len() as u64;
+        let start = (self.core.record_start().saturating_sub(fed) as usize).min(raw.len());
+        let raw = &raw[start..];
+        let end = raw.len()
+            - raw
+                .iter()
+                .rev()
+                .take_while(|b| self.core.is_terminator(**b))
+                .count();
+        let position = *error.position();
+        self.quarantine.push(BadRow {
+            raw: String::from_utf8_lossy(&raw[..end]).into_owned(),
+            error,
+        });
+        self.error_count += 1;
+        match self.max_errors {
+            Some(max) if self.error_count > max => Err(CsvError::TooManyErrors { max, position }),
+            _ => Ok(()),
+        }
+    }
+
+    // resync after a malformed record by dropping everything up to the next terminator
+    fn skip_record(&mut self) -> Result<()> {
+        self.core.skip_record();
+        let mut record = ByteRecord::new();
+        while self.core.skipping() {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(error) => return Err(CsvError::from_io(error, self.core.position())),
             };
+            if buf.is_empty() {
+                self.core.finish(&mut record)?;
+                break;
+            }
+            let (used, _) = self.core.feed(buf, &mut record);
+            self.raw.extend_from_slice(&buf[..used]);
+            self.reader.consume(used);
         }
+        Ok(())
     }
 
-    pub fn scan(&mut self) -> io::Result<Vec<Token>> {
+    fn read_token(&mut self) -> Result<Option<ByteRecord>> {
+        let mut record = ByteRecord::new();
+        Ok(self.read_token_into(&mut record)?.then_some(record))
+    }
+
+    fn read_token_into(&mut self, record: &mut ByteRecord) -> Result<bool> {
+        record.clear();
         loop {
-            match self.next_char() {
-                Ok(Some(c)) => self.parse_record(c)?,
-                Ok(None) => return Ok(self.tokens.clone()),
-                Err(err) => return Err(err),
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(error) => return Err(CsvError::from_io(error, self.core.position())),
             };
+            if buf.is_empty() {
+                return match self.core.finish(record)? {
+                    true => self.end_token(record).map(|_| true),
+                    false => Ok(false),
+                };
+            }
+            let (used, result) = self.core.feed(buf, record);
+            if self.recover {
+                self.raw.extend_from_slice(&buf[..used]);
+            }
+            self.reader.consume(used);
+            if result? {
+                return self.end_token(record).map(|_| true);
+            }
         }
     }
 
-    pub fn parse(&mut self) -> io::Result<Vec<Token>> {
+    fn end_token(&mut self, record: &mut ByteRecord) -> Result<()> {
+        self.capacity = (record.as_slice().len(), record.len());
+        self.check_record_end(record)
+    }
+
+    pub fn scan(&mut self) -> Result<Vec<Record>> {
+        self.collect()
+    }
+
+    pub fn parse(&mut self) -> Result<Vec<Record>> {
         return self.scan();
     }
 }
 
-impl CsvParser<File> {
-    pub fn from_path(file_path: &PathBuf) -> io::Result<Self> {
-        match file_path.extension() {
-            Some(extension) if extension != "csv" => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "The file must have a .csv extension",
-                ));
+impl<R: Read> Iterator for CsvParser<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_record() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.done = true;
+                None
             }
-            None => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "The file must have an extension",
-                ));
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+pub struct ByteRecords<'a, R: Read> {
+    parser: &'a mut CsvParser<R>,
+}
+
+impl<R: Read> Iterator for ByteRecords<'_, R> {
+    type Item = Result<ByteRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.parser.done {
+            return None;
+        }
+        match self.parser.next_byte_record() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.parser.done = true;
+                None
+            }
+            Err(err) => {
+                self.parser.done = true;
+                Some(Err(err))
             }
-            _ => {}
         }
+    }
+}
+
+// a compression extension comes after the one that says what the file
+// holds, `data.tsv.gz` is read as a .tsv
+fn data_extension(file_path: &Path) -> Option<String> {
+    if Compression::from_path(file_path).is_none() {
+        return Some(file_path.extension()?.to_str()?.to_ascii_lowercase());
+    }
+    let stem = Path::new(file_path.file_stem()?);
+    Some(stem.extension()?.to_str()?.to_ascii_lowercase())
+}
+
+// for readers that have to seek in the file and can not decompress it
+fn check_uncompressed(file_path: &Path, data: &[u8]) -> io::Result<()> {
+    match Compression::detect(data).or(Compression::from_path(file_path)) {
+        Some(compression) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The file is {compression} compressed, read it with CsvParser::from_path"),
+        )),
+        None => Ok(()),
+    }
+}
+
+// tab for a .tsv, anything else starts out as RFC 4180
+fn path_dialect(file_path: &Path) -> Dialect {
+    match data_extension(file_path).as_deref() {
+        Some("tsv" | "tab") => Dialect::new().delimiter(b'\t'),
+        _ => Dialect::new(),
+    }
+}
+
+fn check_extension(file_path: &Path) -> io::Result<()> {
+    match data_extension(file_path).as_deref() {
+        Some("csv" | "tsv") => Ok(()),
+        Some(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The file must have a .csv or .tsv extension",
+        )),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The file must have an extension",
+        )),
+    }
+}
+
+impl CsvParser<Decompress<File>> {
+    // any path opens, the extension only picks the dialect. a compressed file
+    // is decompressed while it is read, found by its first bytes or extension
+    pub fn from_path(file_path: &PathBuf) -> io::Result<Self> {
+        Self::from_path_with_dialect(file_path, path_dialect(file_path))
+    }
+
+    // like `from_path` but only a .csv or .tsv, compressed or not, is opened
+    pub fn from_path_strict(file_path: &PathBuf) -> io::Result<Self> {
+        check_extension(file_path)?;
+        Self::from_path(file_path)
+    }
+
+    pub fn from_path_with_dialect(file_path: &PathBuf, dialect: Dialect) -> io::Result<Self> {
+        Ok(Self::new(Decompress::from_path(file_path)?).dialect(dialect))
+    }
 
-        let file = File::open(file_path)?;
-        Ok(Self::new(file))
+    // runs the file through `Sniffer` first and reads it with what it found
+    pub fn from_path_sniffed(file_path: &PathBuf) -> io::Result<Self> {
+        let parser = Self::from_path(file_path)?;
+        let sniffed = Sniffer::new().sniff(Decompress::from_path(file_path)?)?;
+        Ok(parser
+            .dialect(sniffed.dialect)
+            .with_header(sniffed.has_header))
     }
 }
 
@@ -1,7 +1,6 @@
 use std::io;
 
 use clap::Parser;
-use rust_csv_parser;
 
 #[derive(Parser)]
 struct Cli {
@@ -12,18 +11,152 @@ struct Cli {
 
     #[clap(short, long, default_value_t = false)]
     whitespace_empty: bool,
+
+    #[clap(long, default_value_t = false)]
+    header: bool,
+
+    #[clap(short, long, default_value_t = false)]
+    flexible: bool,
+
+    #[clap(long, default_value_t = false)]
+    lossy: bool,
+
+    #[clap(short, long)]
+    encoding: Option<String>,
+
+    #[clap(short, long, default_value_t = false)]
+    recover: bool,
+
+    #[clap(long)]
+    max_errors: Option<u64>,
+
+    // guess the delimiter, quote and header from the start of the file
+    #[clap(short, long, default_value_t = false)]
+    sniff: bool,
+
+    #[clap(short, long)]
+    delimiter: Option<char>,
+
+    #[clap(short, long)]
+    quote: Option<char>,
+
+    // doubled, backslash or none
+    #[clap(long)]
+    escape: Option<String>,
+
+    // quotes are read as plain characters
+    #[clap(long, default_value_t = false)]
+    no_quoting: bool,
+
+    // only open a .csv or .tsv
+    #[clap(long, default_value_t = false)]
+    strict: bool,
+
+    // lines that start with this character are skipped
+    #[clap(long)]
+    comment: Option<char>,
+
+    #[clap(long, default_value_t = 0)]
+    skip_rows: u64,
+
+    // read empty lines as a record with one empty field
+    #[clap(long, default_value_t = false)]
+    keep_blank_lines: bool,
+}
+
+fn ascii(c: char) -> io::Result<u8> {
+    match u8::try_from(c) {
+        Ok(b) if b.is_ascii() => Ok(b),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{c:?} is not an ascii character"),
+        )),
+    }
 }
 
 fn main() -> io::Result<()> {
     let args = Cli::parse();
 
-    let mut parser = rust_csv_parser::CsvParser::from_path(&args.path)?
+    let mut parser = match args.strict {
+        true => rust_csv_parser::CsvParser::from_path_strict(&args.path)?,
+        false => rust_csv_parser::CsvParser::from_path(&args.path)?,
+    };
+    if args.sniff {
+        let file = rust_csv_parser::Decompress::from_path(&args.path)?;
+        let sniffed = rust_csv_parser::Sniffer::new().sniff(file)?;
+        parser = parser
+            .dialect(sniffed.dialect)
+            .with_header(sniffed.has_header);
+    }
+    if args.header {
+        parser = parser.with_header(true);
+    }
+    if let Some(delimiter) = args.delimiter {
+        parser = parser.delimiter(ascii(delimiter)?);
+    }
+    if let Some(quote) = args.quote {
+        parser = parser.quote(ascii(quote)?);
+    }
+    if let Some(label) = args.escape {
+        match rust_csv_parser::Escape::for_label(&label) {
+            Some(escaping) => parser = parser.escaping(escaping),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown escape {label:?}"),
+                ))
+            }
+        }
+    }
+    if args.no_quoting {
+        parser = parser.quoting(false);
+    }
+    if let Some(comment) = args.comment {
+        parser = parser.comment(Some(ascii(comment)?));
+    }
+    let mut parser = parser
         .trim_space(args.trimmer)
-        .all_whitespace_empty(args.whitespace_empty);
+        .all_whitespace_empty(args.whitespace_empty)
+        .flexible(args.flexible)
+        .utf8(match args.lossy {
+            true => rust_csv_parser::Utf8Mode::Lossy,
+            false => rust_csv_parser::Utf8Mode::Strict,
+        })
+        .skip_rows(args.skip_rows)
+        .skip_blank_lines(!args.keep_blank_lines)
+        .recover(args.recover);
+    if let Some(max) = args.max_errors {
+        parser = parser.max_errors(max);
+    }
+    if let Some(label) = args.encoding {
+        match rust_csv_parser::Encoding::for_label(&label) {
+            Some(encoding) => parser = parser.encoding(encoding),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown encoding {label:?}"),
+                ))
+            }
+        }
+    }
+
+    let headers = parser.headers()?.map(<[String]>::to_vec);
+    for line in parser.preamble() {
+        println!("{line}");
+    }
+    if let Some(headers) = headers {
+        println!("{headers:?}");
+    }
 
-    let tokens = parser.parse()?;
-    for token in tokens {
+    for token in parser.by_ref() {
+        let token = token?;
         println!("{token:?}");
     }
+    for warning in parser.warnings() {
+        eprintln!("warning: {warning}");
+    }
+    for bad in parser.quarantine() {
+        eprintln!("rejected {:?}: {}", bad.raw, bad.error);
+    }
     Ok(())
 }
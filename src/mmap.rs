@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+use memmap2::Mmap;
+
+use crate::{check_uncompressed, path_dialect, CsvParser, Dialect, SliceParser};
+
+// a whole file mapped read-only into memory, the OS page cache is read
+// directly instead of copying the file through a buffer. the file must not
+// be changed or truncated while it is mapped
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Option<Mmap>,
+    // picked by the extension like `from_path` does
+    dialect: Dialect,
+}
+
+impl MappedFile {
+    pub fn open(file_path: &PathBuf) -> io::Result<Self> {
+        let file = File::open(file_path)?;
+        let dialect = path_dialect(file_path);
+        // an empty file can not be mapped on every platform
+        if file.metadata()?.len() == 0 {
+            return Ok(MappedFile { map: None, dialect });
+        }
+        // the mapping is only ever read, the file staying the same while it
+        // is mapped is up to the caller
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedFile {
+            map: Some(map),
+            dialect,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or_default()
+    }
+
+    // records whose fields point straight into the mapped file
+    pub fn records(&self) -> SliceParser<'_> {
+        SliceParser::new(self.as_bytes()).dialect(self.dialect)
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl CsvParser<io::Cursor<MappedFile>> {
+    // like `from_path` but reads the file through a memory map. the bytes are
+    // still copied through the decoder's buffer on their way to the records,
+    // `MappedFile::records` borrows the fields from the mapping instead
+    pub fn from_path_mmap(file_path: &PathBuf) -> io::Result<Self> {
+        let file = MappedFile::open(file_path)?;
+        check_uncompressed(file_path, file.as_bytes())?;
+        let dialect = file.dialect;
+        Ok(Self::new(io::Cursor::new(file)).dialect(dialect))
+    }
+}
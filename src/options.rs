@@ -0,0 +1,197 @@
+use crate::{CsvCore, Dialect, Escape, RaggedRows, Terminator};
+
+// the settings every parser reads with, built the same way as a `Dialect`.
+// each parser has these methods too, from `options_setters`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub dialect: Dialect,
+    pub has_header: bool,
+    pub trim_space: bool,
+    pub all_whitespace_empty: bool,
+    pub comment: Option<u8>,
+    pub skip_rows: u64,
+    pub skip_blank_lines: bool,
+    pub ragged: RaggedRows,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            dialect: Dialect::default(),
+            has_header: false,
+            trim_space: false,
+            all_whitespace_empty: false,
+            comment: None,
+            skip_rows: 0,
+            skip_blank_lines: true,
+            ragged: RaggedRows::Error,
+        }
+    }
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Options::default()
+    }
+
+    pub fn with_header(mut self, flag: bool) -> Self {
+        self.has_header = flag;
+        self
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.dialect = self.dialect.delimiter(delimiter);
+        self
+    }
+
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.dialect = self.dialect.quote(quote);
+        self
+    }
+
+    pub fn escape(mut self, escape: Option<u8>) -> Self {
+        self.dialect = self.dialect.escape(escape);
+        self
+    }
+
+    pub fn escaping(mut self, escaping: Escape) -> Self {
+        self.dialect = self.dialect.escaping(escaping);
+        self
+    }
+
+    pub fn quoting(mut self, flag: bool) -> Self {
+        self.dialect = self.dialect.quoting(flag);
+        self
+    }
+
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.dialect = self.dialect.terminator(terminator);
+        self
+    }
+
+    pub fn trim_space(mut self, flag: bool) -> Self {
+        self.trim_space = flag;
+        self
+    }
+
+    pub fn all_whitespace_empty(mut self, flag: bool) -> Self {
+        self.all_whitespace_empty = flag;
+        self
+    }
+
+    // lines that start with `comment` are not records
+    pub fn comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    // the first `rows` lines are dropped before the header is read
+    pub fn skip_rows(mut self, rows: u64) -> Self {
+        self.skip_rows = rows;
+        self
+    }
+
+    // on by default, when off an empty line is a record with one empty field
+    pub fn skip_blank_lines(mut self, flag: bool) -> Self {
+        self.skip_blank_lines = flag;
+        self
+    }
+
+    pub fn flexible(mut self, flag: bool) -> Self {
+        self.ragged = match flag {
+            true => RaggedRows::Accept,
+            false => RaggedRows::Error,
+        };
+        self
+    }
+
+    pub fn ragged_rows(mut self, policy: RaggedRows) -> Self {
+        self.ragged = policy;
+        self
+    }
+
+    // a tokenizer that reads with these options
+    pub(crate) fn core(&self) -> CsvCore {
+        CsvCore::new(self.dialect)
+            .trim_space(self.trim_space)
+            .all_whitespace_empty(self.all_whitespace_empty)
+            .comment(self.comment)
+            .skip_rows(self.skip_rows)
+            .skip_blank_lines(self.skip_blank_lines)
+    }
+}
+
+// the methods of `Options` on a parser, which needs a `set_options` that
+// hands its options to a closure and keeps what comes back
+macro_rules! options_setters {
+    () => {
+        pub fn options(self, options: $crate::Options) -> Self {
+            self.set_options(|_| options)
+        }
+
+        pub fn with_header(self, flag: bool) -> Self {
+            self.set_options(|options| options.with_header(flag))
+        }
+
+        pub fn dialect(self, dialect: $crate::Dialect) -> Self {
+            self.set_options(|options| options.dialect(dialect))
+        }
+
+        pub fn delimiter(self, delimiter: u8) -> Self {
+            self.set_options(|options| options.delimiter(delimiter))
+        }
+
+        pub fn quote(self, quote: u8) -> Self {
+            self.set_options(|options| options.quote(quote))
+        }
+
+        pub fn escape(self, escape: Option<u8>) -> Self {
+            self.set_options(|options| options.escape(escape))
+        }
+
+        pub fn escaping(self, escaping: $crate::Escape) -> Self {
+            self.set_options(|options| options.escaping(escaping))
+        }
+
+        pub fn quoting(self, flag: bool) -> Self {
+            self.set_options(|options| options.quoting(flag))
+        }
+
+        pub fn terminator(self, terminator: $crate::Terminator) -> Self {
+            self.set_options(|options| options.terminator(terminator))
+        }
+
+        pub fn trim_space(self, flag: bool) -> Self {
+            self.set_options(|options| options.trim_space(flag))
+        }
+
+        pub fn all_whitespace_empty(self, flag: bool) -> Self {
+            self.set_options(|options| options.all_whitespace_empty(flag))
+        }
+
+        pub fn comment(self, comment: Option<u8>) -> Self {
+            self.set_options(|options| options.comment(comment))
+        }
+
+        pub fn skip_rows(self, rows: u64) -> Self {
+            self.set_options(|options| options.skip_rows(rows))
+        }
+
+        pub fn skip_blank_lines(self, flag: bool) -> Self {
+            self.set_options(|options| options.skip_blank_lines(flag))
+        }
+
+        pub fn flexible(self, flag: bool) -> Self {
+            self.set_options(|options| options.flexible(flag))
+        }
+
+        pub fn ragged_rows(self, policy: $crate::RaggedRows) -> Self {
+            self.set_options(|options| options.ragged_rows(policy))
+        }
+    };
+}
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::encoding::Decoder;
+use crate::{
+    check_uncompressed, path_dialect, CsvCore, CsvError, CsvParser, Encoding, Options, Position,
+    Record, Result, Utf8Mode,
+};
+
+// a piece of the file that starts and ends on a record boundary, `core` is
+// the tokenizer as it was at `start`
+#[derive(Debug)]
+struct Chunk {
+    start: u64,
+    end: u64,
+    core: CsvCore,
+}
+
+// what a worker made of one chunk, the records before an error are kept
+#[derive(Debug)]
+struct Batch {
+    records: Vec<Record>,
+    warnings: Vec<CsvError>,
+    error: Option<CsvError>,
+}
+
+// walks the file ahead of the workers to find where chunks end. a worker
+// takes it, cuts off the next chunk and lets it go before reading that chunk.
+// only one worker at a time can, so the skim has to stay well ahead of the
+// parsing for more threads to help
+#[derive(Debug)]
+struct Scan {
+    reader: io::BufReader<File>,
+    core: CsvCore,
+    offset: u64,
+    // chunks cut off so far
+    index: usize,
+    done: bool,
+    // one is taken for every chunk and handed back when its records are
+    // given out, this caps how much of the file is held in memory
+    permits: mpsc::Receiver<()>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    path: PathBuf,
+    template: CsvParser<io::Empty>,
+    chunk_size: u64,
+    scan: Mutex<Scan>,
+}
+
+#[derive(Debug)]
+struct Running {
+    shared: Arc<Shared>,
+    permits: mpsc::Sender<()>,
+    results: mpsc::Receiver<(usize, Batch)>,
+    // finished chunks waiting for the ones before them
+    ready: BTreeMap<usize, Batch>,
+    next: usize,
+}
+
+// reads a file on several threads. the workers take turns cutting the file
+// into chunks on record boundaries and every chunk gets its own parser, the
+// records come out in file order unless `ordered(false)` lets them out as
+// soon as a chunk is done. the file has to be UTF-8
+#[derive(Debug)]
+pub struct ParallelParser {
+    path: PathBuf,
+    // holds the settings every chunk is read with
+    template: CsvParser<io::Empty>,
+    threads: usize,
+    chunk_size: u64,
+    ordered: bool,
+    running: Option<Running>,
+    preamble: Vec<String>,
+    records: std::vec::IntoIter<Record>,
+    error: Option<CsvError>,
+    warnings: Vec<CsvError>,
+    done: bool,
+}
+
+impl ParallelParser {
+    pub fn from_path(file_path: &PathBuf) -> io::Result<Self> {
+        let mut magic = Vec::with_capacity(10);
+        File::open(file_path)?.take(10).read_to_end(&mut magic)?;
+        check_uncompressed(file_path, &magic)?;
+        Ok(ParallelParser {
+            path: file_path.clone(),
+            template: CsvParser::new(io::empty()).dialect(path_dialect(file_path)),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            chunk_size: 8 * 1024 * 1024,
+            ordered: true,
+            running: None,
+            preamble: Vec::new(),
+            records: Vec::new().into_iter(),
+            error: None,
+            warnings: Vec::new(),
+            done: false,
+        })
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // about how many bytes each thread reads at a time
+    pub fn chunk_size(mut self, size: u64) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    pub fn ordered(mut self, flag: bool) -> Self {
+        self.ordered = flag;
+        self
+    }
+
+    fn set(mut self, f: impl FnOnce(CsvParser<io::Empty>) -> CsvParser<io::Empty>) -> Self {
+        self.template = f(self.template);
+        self
+    }
+
+    options_setters!();
+
+    fn set_options(self, f: impl FnOnce(Options) -> Options) -> Self {
+        self.set(|parser| parser.set_options(f))
+    }
+
+    pub fn utf8(self, mode: Utf8Mode) -> Self {
+        self.set(|parser| parser.utf8(mode))
+    }
+
+    // the lines dropped before the first record, known once it was read
+    pub fn preamble(&self) -> &[String] {
+        &self.preamble
+    }
+
+    // irregular records let through by the ragged rows policy, for the
+    // chunks that were given out so far
+    pub fn warnings(&self) -> &[CsvError] {
+        &self.warnings
+    }
+
+    // reads the header, or the first record for the field count, on this
+    // thread and starts the workers on the rest of the file
+    fn start(&mut self) -> Result<Running> {
+        let mut head = Vec::new();
+        File::open(&self.path)?.take(3).read_to_end(&mut head)?;
+        let bom = match head.as_slice() {
+            b"\xEF\xBB\xBF" => 3,
+            _ => 0,
+        };
+
+        let mut first = self
+            .template
+            .with_reader(Decoder::new(File::open(&self.path)?));
+        let core = match first.options.has_header {
+            true => {
+                first.read_headers()?;
+                first.core.clone()
+            }
+            false => {
+                first.read_token()?;
+                self.template.core.clone()
+            }
+        };
+        if let Some(found) = first
+            .input_encoding()
+            .filter(|found| *found != Encoding::Utf8)
+        {
+            return Err(CsvError::EncodingMismatch {
+                expected: Encoding::Utf8,
+                found,
+                position: Position::new(0, 1, 1, 0),
+            });
+        }
+        self.preamble = first.preamble().to_vec();
+        self.template.options.has_header = false;
+        self.template.headers = first.headers.clone();
+        self.template.record_field_count = first.record_field_count;
+
+        let offset = core.position().byte() + bom;
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let (permits, queue) = mpsc::channel();
+        for _ in 0..2 * self.threads {
+            let _ = permits.send(());
+        }
+        let scan = Scan {
+            reader: io::BufReader::with_capacity(1024 * 1024, file),
+            core,
+            offset,
+            index: 0,
+            done: false,
+            permits: queue,
+        };
+        let shared = Arc::new(Shared {
+            path: self.path.clone(),
+            template: self.template.with_reader(Decoder::new(io::empty())),
+            chunk_size: self.chunk_size,
+            scan: Mutex::new(scan),
+        });
+
+        let (done, results) = mpsc::channel();
+        for _ in 0..self.threads {
+            let shared = Arc::clone(&shared);
+            let done = done.clone();
+            thread::spawn(move || work(&shared, done));
+        }
+        Ok(Running {
+            shared,
+            permits,
+            results,
+            ready: BTreeMap::new(),
+            next: 0,
+        })
+    }
+
+    fn next_batch(&mut self) -> Result<Option<Batch>> {
+        if self.running.is_none() {
+            self.running = Some(self.start()?);
+        }
+        let Some(running) = self.running.as_mut() else {
+            return Ok(None);
+        };
+        let batch = loop {
+            if let Some(batch) = running.ready.remove(&running.next) {
+                break batch;
+            }
+            match running.results.recv() {
+                Ok((index, batch)) if self.ordered => {
+                    running.ready.insert(index, batch);
+                }
+                Ok((_, batch)) => break batch,
+                // the workers are gone, which is fine once every chunk they
+                // cut off was given out
+                Err(_) => {
+                    let scan = running.shared.scan.lock().map_err(|_| stopped())?;
+                    return match running.next < scan.index {
+                        true => Err(stopped()),
+                        false => Ok(None),
+                    };
+                }
+            }
+        };
+        running.next += 1;
+        let _ = running.permits.send(());
+        Ok(Some(batch))
+    }
+}
+
+impl Iterator for ParallelParser {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.records.next() {
+                return Some(Ok(record));
+            }
+            if let Some(error) = self.error.take() {
+                self.done = true;
+                return Some(Err(error));
+            }
+            if self.done {
+                return None;
+            }
+            match self.next_batch() {
+                Ok(Some(batch)) => {
+                    self.records = batch.records.into_iter();
+                    self.warnings.extend(batch.warnings);
+                    self.error = batch.error;
+                }
+                Ok(None) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+impl CsvParser<io::Empty> {
+    // a parser with the same settings that reads from `reader`
+    fn with_reader<S: Read>(&self, reader: Decoder<S>) -> CsvParser<S> {
+        CsvParser {
+            reader: io::BufReader::with_capacity(64 * 1024, reader),
+            options: self.options.clone(),
+            core: self.core.clone(),
+            capacity: (0, 0),
+            record_field_count: self.record_field_count,
+            headers: self.headers.clone(),
+            #[cfg(feature = "serde")]
+            header_separator: self.header_separator.clone(),
+            utf8: self.utf8,
+            warnings: Vec::new(),
+            recover: false,
+            max_errors: None,
+            error_count: 0,
+            raw: Vec::new(),
+            quarantine: Vec::new(),
+            done: false,
+        }
+    }
+}
+
+fn work(shared: &Shared, done: mpsc::Sender<(usize, Batch)>) {
+    loop {
+        let next = match shared.scan.lock() {
+            Ok(mut scan) => scan.next_chunk(shared.chunk_size),
+            Err(_) => return,
+        };
+        let (index, batch) = match next {
+            Some((index, Ok(chunk))) => (index, read_chunk(shared, chunk)),
+            Some((index, Err(error))) => (
+                index,
+                Batch {
+                    records: Vec::new(),
+                    warnings: Vec::new(),
+                    error: Some(error.into()),
+                },
+            ),
+            None => return,
+        };
+        if done.send((index, batch)).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_chunk(shared: &Shared, chunk: Chunk) -> Batch {
+    let mut batch = Batch {
+        records: Vec::new(),
+        warnings: Vec::new(),
+        error: None,
+    };
+    let mut file = match File::open(&shared.path) {
+        Ok(file) => file,
+        Err(error) => {
+            batch.error = Some(error.into());
+            return batch;
+        }
+    };
+    if let Err(error) = file.seek(SeekFrom::Start(chunk.start)) {
+        batch.error = Some(error.into());
+        return batch;
+    }
+    let reader = Decoder::utf8_tail(file.take(chunk.end - chunk.start));
+    let mut parser = shared.template.with_reader(reader);
+    parser.core = chunk.core;
+    for record in parser.by_ref() {
+        match record {
+            Ok(record) => batch.records.push(record),
+            Err(error) => batch.error = Some(error),
+        }
+    }
+    batch.warnings = std::mem::take(&mut parser.warnings);
+    batch
+}
+
+fn stopped() -> CsvError {
+    CsvError::from(io::Error::other("a parser thread stopped"))
+}
+
+impl Scan {
+    // skims ahead to the end of the first record that ends `size` bytes or
+    // more from here. `None` once the file is used up or the parser went away
+    fn next_chunk(&mut self, size: u64) -> Option<(usize, io::Result<Chunk>)> {
+        if self.done || self.permits.recv().is_err() {
+            self.done = true;
+            return None;
+        }
+        let (start, core) = (self.offset, self.core.clone());
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(error) => {
+                    self.done = true;
+                    self.index += 1;
+                    return Some((self.index - 1, Err(error)));
+                }
+            };
+            if buf.is_empty() {
+                self.done = true;
+                break;
+            }
+            let until = size.saturating_sub(self.offset - start);
+            let (used, ended) = self.core.skim(buf, until.try_into().unwrap_or(usize::MAX));
+            self.reader.consume(used);
+            self.offset += used as u64;
+            if ended {
+                break;
+            }
+        }
+        if self.offset == start {
+            return None;
+        }
+        self.index += 1;
+        let chunk = Chunk {
+            start,
+            end: self.offset,
+            core,
+        };
+        Some((self.index - 1, Ok(chunk)))
+    }
+}
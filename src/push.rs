@@ -0,0 +1,183 @@
+use std::io;
+
+use crate::{ByteRecord, CsvError, CsvParser, Options, Record, Result, Utf8Mode};
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// parses input that arrives in chunks of any size, from a socket or a queue.
+// a field, an open quote or a '\r' at the end of one chunk is carried over to
+// the next, the records come out the same as `CsvParser` gives them
+#[derive(Debug)]
+pub struct PushParser {
+    // holds the settings and everything kept between records, its own
+    // reader is never read
+    parser: CsvParser<io::Empty>,
+    // the record being read, it is kept until the chunk that ends it
+    record: ByteRecord,
+    // the start of the input while it could still be a BOM
+    head: Vec<u8>,
+    started: bool,
+    // an error that came after some records of a chunk, those are handed
+    // out first and it is returned by the next call
+    error: Option<CsvError>,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushParser {
+    pub fn new() -> Self {
+        PushParser {
+            parser: CsvParser::new(io::empty()),
+            record: ByteRecord::new(),
+            head: Vec::new(),
+            started: false,
+            error: None,
+        }
+    }
+
+    fn set(mut self, f: impl FnOnce(CsvParser<io::Empty>) -> CsvParser<io::Empty>) -> Self {
+        self.parser = f(self.parser);
+        self
+    }
+
+    options_setters!();
+
+    pub(crate) fn set_options(self, f: impl FnOnce(Options) -> Options) -> Self {
+        self.set(|parser| parser.set_options(f))
+    }
+
+    pub fn utf8(self, mode: Utf8Mode) -> Self {
+        self.set(|parser| parser.utf8(mode))
+    }
+
+    pub fn warnings(&self) -> &[CsvError] {
+        self.parser.warnings()
+    }
+
+    pub fn preamble(&self) -> &[String] {
+        self.parser.preamble()
+    }
+
+    // `None` until the header row has been fed in full
+    pub fn headers(&self) -> Option<&[String]> {
+        self.parser
+            .headers
+            .as_ref()
+            .map(|headers| headers.as_slice())
+    }
+
+    // true once `finish` was called or an error was returned, anything fed
+    // after that is ignored
+    pub fn is_done(&self) -> bool {
+        self.parser.done && self.error.is_none()
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn take_error(&mut self) -> Option<CsvError> {
+        self.error.take()
+    }
+
+    // stops the parser on an error reading the input
+    #[cfg(feature = "async")]
+    pub(crate) fn fail(&mut self, error: io::Error) -> CsvError {
+        self.parser.done = true;
+        CsvError::from_io(error, self.parser.core.position())
+    }
+
+    pub(crate) fn wants_headers(&self) -> bool {
+        self.parser.options.has_header && self.parser.headers.is_none() && !self.is_done()
+    }
+
+    // the records that `input` completes, which can be none
+    pub fn feed(&mut self, input: &[u8]) -> Result<Vec<Record>> {
+        let mut records = Vec::new();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if self.parser.done {
+            return Ok(records);
+        }
+        let result = match self.started {
+            true => self.push(input, &mut records),
+            false => {
+                let mut head = std::mem::take(&mut self.head);
+                head.extend_from_slice(input);
+                // wait for enough bytes to know if it starts with a BOM
+                if head.len() < BOM.len() && BOM.starts_with(&head) {
+                    self.head = head;
+                    return Ok(records);
+                }
+                self.started = true;
+                self.push(head.strip_prefix(BOM).unwrap_or(&head), &mut records)
+            }
+        };
+        self.end_chunk(result, records)
+    }
+
+    // the end of the input, returns the last record if it had no terminator
+    pub fn finish(&mut self) -> Result<Vec<Record>> {
+        let mut records = Vec::new();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if self.parser.done {
+            return Ok(records);
+        }
+        let head = std::mem::take(&mut self.head);
+        self.started = true;
+        let result = self.push(&head, &mut records).and_then(|_| {
+            if self.parser.core.finish(&mut self.record)? {
+                self.end_record(&mut records)?;
+            }
+            match self.wants_headers() {
+                true => self.parser.set_header_row(None),
+                false => Ok(()),
+            }
+        });
+        self.parser.done = true;
+        self.end_chunk(result, records)
+    }
+
+    fn end_chunk(&mut self, result: Result<()>, records: Vec<Record>) -> Result<Vec<Record>> {
+        let error = match result {
+            Ok(()) => return Ok(records),
+            Err(error) => error,
+        };
+        self.parser.done = true;
+        if records.is_empty() {
+            return Err(error);
+        }
+        self.error = Some(error);
+        Ok(records)
+    }
+
+    fn push(&mut self, mut input: &[u8], records: &mut Vec<Record>) -> Result<()> {
+        while !input.is_empty() {
+            let (used, result) = self.parser.core.feed(input, &mut self.record);
+            input = &input[used..];
+            if result? {
+                self.end_record(records)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end_record(&mut self, records: &mut Vec<Record>) -> Result<()> {
+        // sized like the last record so it is allocated once
+        let (bytes, fields) = self.parser.capacity;
+        let next = ByteRecord::with_capacity(bytes, fields);
+        let mut token = std::mem::replace(&mut self.record, next);
+        self.parser.end_token(&mut token)?;
+        if self.wants_headers() {
+            return self.parser.set_header_row(Some(token));
+        }
+        let mut record = self.parser.to_record(token)?;
+        record.set_headers(self.parser.headers.clone());
+        records.push(record);
+        Ok(())
+    }
+}
@@ -0,0 +1,606 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::tokenizer::Fields;
+use crate::CsvError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    record: u64,
+    line: u64,
+    column: u64,
+    byte: u64,
+}
+
+impl Position {
+    pub fn new(record: u64, line: u64, column: u64, byte: u64) -> Self {
+        Position {
+            record,
+            line,
+            column,
+            byte,
+        }
+    }
+
+    // for errors that come before the input is split into records and lines
+    pub(crate) fn from_byte(byte: u64) -> Self {
+        Position::new(0, 0, 0, byte)
+    }
+
+    pub fn record(&self) -> u64 {
+        self.record
+    }
+
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    pub fn column(&self) -> u64 {
+        self.column
+    }
+
+    pub fn byte(&self) -> u64 {
+        self.byte
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "record {} line {} column {} byte {}",
+            self.record, self.line, self.column, self.byte
+        )
+    }
+}
+
+// all fields are stored back to back in one string, `ends[i]` is where
+// field `i` stops so no allocation is needed per field
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    fields: String,
+    ends: Vec<usize>,
+    starts: Vec<u64>,
+    position: Position,
+    headers: Option<Arc<Vec<String>>>,
+}
+
+pub trait FieldIndex {
+    fn field_index(&self, record: &Record) -> Option<usize>;
+}
+
+impl FieldIndex for usize {
+    fn field_index(&self, record: &Record) -> Option<usize> {
+        if *self < record.len() {
+            Some(*self)
+        } else {
+            None
+        }
+    }
+}
+
+impl FieldIndex for &str {
+    fn field_index(&self, record: &Record) -> Option<usize> {
+        let headers = record.headers.as_ref()?;
+        headers.iter().position(|header| header == self)
+    }
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Record::default()
+    }
+
+    pub fn with_capacity(bytes: usize, fields: usize) -> Self {
+        Record {
+            fields: String::with_capacity(bytes),
+            ends: Vec::with_capacity(fields),
+            starts: Vec::with_capacity(fields),
+            position: Position::default(),
+            headers: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn get<I: FieldIndex>(&self, index: I) -> Option<&str> {
+        let i = index.field_index(self)?;
+        self.fields.get(self.range(i)?)
+    }
+
+    pub fn iter(&self) -> RecordIter<'_> {
+        RecordIter {
+            record: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    // every field joined together without separators
+    pub fn as_slice(&self) -> &str {
+        &self.fields
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn headers(&self) -> Option<&[String]> {
+        self.headers.as_ref().map(|headers| headers.as_slice())
+    }
+
+    pub fn push_field(&mut self, field: &str) {
+        self.fields.push_str(field);
+        self.ends.push(self.fields.len());
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.ends.truncate(len);
+            self.starts.truncate(len);
+            let end = self.ends.last().copied().unwrap_or(0);
+            self.fields.truncate(end);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+        self.ends.clear();
+        self.starts.clear();
+    }
+
+    pub(crate) fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    pub(crate) fn set_headers(&mut self, headers: Option<Arc<Vec<String>>>) {
+        self.headers = headers;
+    }
+
+    fn range(&self, i: usize) -> Option<Range<usize>> {
+        field_range(&self.ends, i)
+    }
+
+    pub fn from_byte_record(record: ByteRecord) -> Result<Record, CsvError> {
+        let ByteRecord {
+            fields,
+            ends,
+            starts,
+            position,
+        } = record;
+        match String::from_utf8(fields) {
+            Ok(fields) => Ok(Record {
+                fields,
+                ends,
+                starts,
+                position,
+                headers: None,
+            }),
+            Err(err) => {
+                let bad = err.utf8_error().valid_up_to();
+                Err(invalid_utf8(err.as_bytes(), &ends, &starts, position, bad))
+            }
+        }
+    }
+
+    pub fn from_byte_record_lossy(record: ByteRecord) -> Record {
+        let ByteRecord {
+            fields,
+            ends,
+            starts,
+            position,
+        } = record;
+        match String::from_utf8(fields) {
+            Ok(fields) => Record {
+                fields,
+                ends,
+                starts,
+                position,
+                headers: None,
+            },
+            // replace field by field so a bad sequence never swallows a field boundary
+            Err(err) => {
+                let fields = err.into_bytes();
+                let mut ret = Record::new();
+                for i in 0..ends.len() {
+                    let range = field_range(&ends, i).unwrap_or_default();
+                    ret.push_field(&String::from_utf8_lossy(&fields[range]));
+                }
+                ret.starts = starts;
+                ret.position = position;
+                ret
+            }
+        }
+    }
+}
+
+fn field_range(ends: &[usize], i: usize) -> Option<Range<usize>> {
+    let end = *ends.get(i)?;
+    let start = match i {
+        0 => 0,
+        _ => ends[i - 1],
+    };
+    Some(start..end)
+}
+
+// the error for the invalid byte at `bad` in `fields`. its place in the input
+// is worked out from where its field starts, the bytes the fields leave out
+// (quotes, delimiters, escapes and trimmed spaces) are all one ASCII char so
+// a column is the chars of the fields plus the bytes that are missing
+fn invalid_utf8(
+    fields: &[u8],
+    ends: &[usize],
+    starts: &[u64],
+    position: Position,
+    bad: usize,
+) -> CsvError {
+    let field = ends.iter().take_while(|end| **end <= bad).count();
+    let source = |i: usize, field: usize| {
+        let from = field_range(ends, field).map_or(0, |range| range.start);
+        starts.get(field).map(|start| start + (i - from) as u64)
+    };
+    let byte = match source(bad, field) {
+        Some(byte) => byte,
+        None => {
+            return CsvError::InvalidUtf8 {
+                field: field as u64 + 1,
+                position,
+            }
+        }
+    };
+    let chars = |bytes: &[u8]| bytes.iter().filter(|b| **b as i8 >= -0x40).count() as u64;
+    // the last line break before the bad byte, every field is counted on its
+    // own so a '\r' and a '\n' in two fields are two lines
+    let mut lines = 0;
+    let mut last = None;
+    for i in 0..=field {
+        let range = field_range(ends, i).unwrap_or_default();
+        let bytes = &fields[range.start..range.end.min(bad)];
+        for at in memchr::memchr2_iter(b'\r', b'\n', bytes) {
+            if !(bytes[at] == b'\n' && at > 0 && bytes[at - 1] == b'\r') {
+                lines += 1;
+            }
+            last = Some(range.start + at);
+        }
+    }
+    let (column, from, after) = match last {
+        Some(at) => {
+            let from = ends.iter().take_while(|end| **end <= at).count();
+            (1, source(at, from).unwrap_or(byte) + 1, at + 1)
+        }
+        None => (position.column(), position.byte(), 0),
+    };
+    let missing = (byte - from.min(byte)).saturating_sub((bad - after) as u64);
+    CsvError::InvalidUtf8 {
+        field: field as u64 + 1,
+        position: Position::new(
+            position.record(),
+            position.line() + lines,
+            column + chars(&fields[after..bad]) + missing,
+            byte,
+        ),
+    }
+}
+
+// how a ByteRecord becomes a Record, `Lossy` swaps invalid UTF-8 for U+FFFD
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Mode {
+    Strict,
+    Lossy,
+}
+
+// same layout as Record but the fields are raw bytes that were never checked for UTF-8
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ByteRecord {
+    fields: Vec<u8>,
+    ends: Vec<usize>,
+    // where every field starts in the input, for the position of a bad byte
+    starts: Vec<u64>,
+    position: Position,
+}
+
+impl ByteRecord {
+    pub fn new() -> Self {
+        ByteRecord::default()
+    }
+
+    pub fn with_capacity(bytes: usize, fields: usize) -> Self {
+        ByteRecord {
+            fields: Vec::with_capacity(bytes),
+            ends: Vec::with_capacity(fields),
+            starts: Vec::with_capacity(fields),
+            position: Position::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[u8]> {
+        self.fields.get(field_range(&self.ends, i)?)
+    }
+
+    // validates only the requested field
+    pub fn get_str(&self, i: usize) -> Option<Result<&str, CsvError>> {
+        let range = field_range(&self.ends, i)?;
+        Some(
+            std::str::from_utf8(&self.fields[range.clone()]).map_err(|err| {
+                let bad = range.start + err.valid_up_to();
+                invalid_utf8(&self.fields, &self.ends, &self.starts, self.position, bad)
+            }),
+        )
+    }
+
+    pub fn iter(&self) -> ByteRecordIter<'_> {
+        ByteRecordIter {
+            record: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.fields
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn push_field(&mut self, field: &[u8]) {
+        self.fields.extend_from_slice(field);
+        self.ends.push(self.fields.len());
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.ends.truncate(len);
+            self.starts.truncate(len);
+            let end = self.ends.last().copied().unwrap_or(0);
+            self.fields.truncate(end);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+        self.ends.clear();
+        self.starts.clear();
+    }
+
+    pub(crate) fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+}
+
+impl Fields for ByteRecord {
+    fn clear(&mut self) {
+        ByteRecord::clear(self);
+    }
+
+    fn start_field(&mut self, at: u64) {
+        self.starts.push(at);
+    }
+
+    // the tokenizer copies a field in pieces, it is only added by `end_field`
+    fn extend_field(&mut self, bytes: &[u8], _at: u64) {
+        self.fields.extend_from_slice(bytes);
+    }
+
+    fn push_byte(&mut self, b: u8) {
+        self.fields.push(b);
+    }
+
+    fn end_field(&mut self, trim_space: bool, all_whitespace_empty: bool) {
+        let start = self.ends.last().copied().unwrap_or(0);
+        let field = &self.fields[start..];
+        let trimmed = field.trim_ascii();
+        if all_whitespace_empty && trimmed.is_empty() {
+            self.fields.truncate(start);
+        } else if trim_space && trimmed.len() != field.len() {
+            let lead = field.len() - field.trim_ascii_start().len();
+            let len = trimmed.len();
+            if let Some(at) = self.starts.last_mut() {
+                *at += lead as u64;
+            }
+            self.fields.drain(start..start + lead);
+            self.fields.truncate(start + len);
+        }
+        self.ends.push(self.fields.len());
+    }
+
+    fn set_position(&mut self, position: Position) {
+        ByteRecord::set_position(self, position);
+    }
+}
+
+impl fmt::Debug for ByteRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteRecord")
+            .field("position", &self.position)
+            .field(
+                "fields",
+                &self.iter().map(String::from_utf8_lossy).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<T: AsRef<[u8]>> From<Vec<T>> for ByteRecord {
+    fn from(fields: Vec<T>) -> Self {
+        let mut record = ByteRecord::new();
+        for field in fields.iter() {
+            record.push_field(field.as_ref());
+        }
+        record
+    }
+}
+
+impl From<Record> for ByteRecord {
+    fn from(record: Record) -> Self {
+        ByteRecord {
+            fields: record.fields.into_bytes(),
+            ends: record.ends,
+            starts: record.starts,
+            position: record.position,
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> PartialEq<[T]> for ByteRecord {
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(field, other)| field == other.as_ref())
+    }
+}
+
+impl<T: AsRef<[u8]>> PartialEq<Vec<T>> for ByteRecord {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self == other.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteRecord {
+    type Item = &'a [u8];
+    type IntoIter = ByteRecordIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct ByteRecordIter<'a> {
+    record: &'a ByteRecord,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for ByteRecordIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let field = self.record.get(self.front);
+        self.front += 1;
+        field
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for ByteRecordIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.record.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for ByteRecordIter<'_> {}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("position", &self.position)
+            .field("fields", &self.iter().collect::<Vec<&str>>())
+            .finish()
+    }
+}
+
+impl<T: AsRef<str>> From<Vec<T>> for Record {
+    fn from(fields: Vec<T>) -> Self {
+        let mut record = Record::new();
+        for field in fields.iter() {
+            record.push_field(field.as_ref());
+        }
+        record
+    }
+}
+
+impl From<Record> for Vec<String> {
+    fn from(record: Record) -> Self {
+        record.iter().map(String::from).collect()
+    }
+}
+
+impl<T: AsRef<str>> PartialEq<[T]> for Record {
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(field, other)| field == other.as_ref())
+    }
+}
+
+impl<T: AsRef<str>> PartialEq<Vec<T>> for Record {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self == other.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a Record {
+    type Item = &'a str;
+    type IntoIter = RecordIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct RecordIter<'a> {
+    record: &'a Record,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for RecordIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let field = self.record.get(self.front);
+        self.front += 1;
+        field
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for RecordIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.record.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for RecordIter<'_> {}
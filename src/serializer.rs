@@ -0,0 +1,491 @@
+use std::fmt;
+use std::io::Write;
+
+use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct};
+use serde::ser::{SerializeTuple, SerializeTupleStruct};
+
+use crate::{ByteRecord, CsvError, CsvWriter, Position, Result};
+
+#[derive(Debug)]
+struct SerError {
+    field: Option<String>,
+    message: String,
+}
+
+impl fmt::Display for SerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SerError {}
+
+impl ser::Error for SerError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        SerError {
+            field: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl<W: Write> CsvWriter<W> {
+    // whether `serialize` starts with a header row of the field names, on by default
+    pub fn with_header(mut self, flag: bool) -> Self {
+        self.has_header = flag;
+        self
+    }
+
+    // nested structs become one column per field named `outer.inner`
+    pub fn header_separator(mut self, separator: &str) -> Self {
+        self.header_separator = separator.to_string();
+        self
+    }
+
+    // the header row is taken from the first value, a tuple or sequence has
+    // no names so it never gets one. later values have to fill the same
+    // columns, a `None` nested struct in the first value is one column so a
+    // later `Some` in its place is an error
+    pub fn serialize<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        let mut record = ByteRecord::new();
+        let mut headers = Vec::new();
+        let mut serializer = RecordSerializer {
+            record: &mut record,
+            headers: &mut headers,
+            columns: &self.columns,
+            path: Vec::new(),
+            separator: &self.header_separator,
+            named: true,
+        };
+        if let Err(err) = value.serialize(&mut serializer) {
+            return Err(CsvError::Serialize {
+                field: err.field,
+                message: err.message,
+                position: Position::new(self.record + 1, 0, 0, 0),
+            });
+        }
+        let named = serializer.named;
+        if named && !self.columns.is_empty() && headers != self.columns {
+            let field = match headers.iter().zip(&self.columns).find(|(a, b)| a != b) {
+                Some((found, _)) => Some(found.clone()),
+                None => headers.get(self.columns.len()).cloned(),
+            };
+            return Err(CsvError::Serialize {
+                field,
+                message: format!(
+                    "the fields do not match the {} columns of the first value",
+                    self.columns.len()
+                ),
+                position: Position::new(self.record + 1, 0, 0, 0),
+            });
+        }
+        if self.has_header && self.record == 0 && named {
+            self.write_record(&headers)?;
+        }
+        if self.columns.is_empty() && named {
+            self.columns = headers;
+        }
+        self.write_record(&record)
+    }
+}
+
+struct RecordSerializer<'a> {
+    record: &'a mut ByteRecord,
+    headers: &'a mut Vec<String>,
+    columns: &'a [String],
+    // the field names leading to the value being written
+    path: Vec<String>,
+    separator: &'a str,
+    named: bool,
+}
+
+impl<'a> RecordSerializer<'a> {
+    fn push(&mut self, field: &[u8]) -> std::result::Result<(), SerError> {
+        self.record.push_field(field);
+        self.named &= !self.path.is_empty();
+        self.headers.push(self.path.join(self.separator));
+        Ok(())
+    }
+
+    fn fail(&self, message: &str) -> SerError {
+        SerError {
+            field: self.field(),
+            message: message.to_string(),
+        }
+    }
+
+    fn field(&self) -> Option<String> {
+        match self.path.is_empty() {
+            true => None,
+            false => Some(self.path.join(self.separator)),
+        }
+    }
+
+    fn value<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), SerError> {
+        let ret = value.serialize(&mut *self).map_err(|mut err| {
+            if err.field.is_none() {
+                err.field = self.field();
+            }
+            err
+        });
+        self.path.pop();
+        ret
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident $ty:ty)*) => {
+        $(
+            fn $method(self, value: $ty) -> std::result::Result<(), SerError> {
+                self.push(value.to_string().as_bytes())
+            }
+        )*
+    };
+}
+
+impl<'a, 'b> ser::Serializer for &'b mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), SerError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), SerError>;
+
+    serialize_display! {
+        serialize_bool bool
+        serialize_i8 i8
+        serialize_i16 i16
+        serialize_i32 i32
+        serialize_i64 i64
+        serialize_i128 i128
+        serialize_u8 u8
+        serialize_u16 u16
+        serialize_u32 u32
+        serialize_u64 u64
+        serialize_u128 u128
+        serialize_char char
+    }
+
+    // Debug keeps the exponent so huge and tiny values stay short
+    fn serialize_f32(self, value: f32) -> std::result::Result<(), SerError> {
+        self.push(format!("{value:?}").as_bytes())
+    }
+
+    fn serialize_f64(self, value: f64) -> std::result::Result<(), SerError> {
+        self.push(format!("{value:?}").as_bytes())
+    }
+
+    fn serialize_str(self, value: &str) -> std::result::Result<(), SerError> {
+        self.push(value.as_bytes())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> std::result::Result<(), SerError> {
+        self.push(value)
+    }
+
+    // a `None` in place of a nested struct is an empty field for every one
+    // of its columns
+    fn serialize_none(self) -> std::result::Result<(), SerError> {
+        let prefix = format!("{}{}", self.path.join(self.separator), self.separator);
+        let columns = self.columns;
+        let mut nested = columns
+            .iter()
+            .filter(|column| !self.path.is_empty() && column.starts_with(&prefix))
+            .peekable();
+        if nested.peek().is_none() {
+            return self.push(b"");
+        }
+        for column in nested {
+            self.record.push_field(b"");
+            self.headers.push(column.clone());
+        }
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> std::result::Result<(), SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> std::result::Result<(), SerError> {
+        self.push(b"")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<(), SerError> {
+        self.push(b"")
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> std::result::Result<(), SerError> {
+        self.push(variant.as_bytes())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> std::result::Result<(), SerError> {
+        Err(self.fail("only unit enum variants can be written to a field"))
+    }
+
+    // the elements become the fields of the record
+    fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<Self, SerError> {
+        match self.path.is_empty() {
+            true => Ok(self),
+            false => Err(self.fail("a sequence can only be written as a whole record")),
+        }
+    }
+
+    fn serialize_tuple(self, len: usize) -> std::result::Result<Self, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, SerError> {
+        Err(self.fail("only unit enum variants can be written to a field"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> std::result::Result<Self, SerError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self, SerError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, SerError> {
+        Err(self.fail("only unit enum variants can be written to a field"))
+    }
+}
+
+impl<'a> SerializeSeq for &mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> std::result::Result<(), SerError> {
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTuple for &mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> std::result::Result<(), SerError> {
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTupleStruct for &mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> std::result::Result<(), SerError> {
+        Ok(())
+    }
+}
+
+impl<'a> SerializeStruct for &mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        self.path.push(key.to_string());
+        self.value(value)
+    }
+
+    fn end(self) -> std::result::Result<(), SerError> {
+        Ok(())
+    }
+}
+
+// maps show up for `#[serde(flatten)]` fields, their keys have to be strings
+impl<'a> SerializeMap for &mut RecordSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T: Serialize + ?Sized>(
+        &mut self,
+        key: &T,
+    ) -> std::result::Result<(), SerError> {
+        let key = key.serialize(KeySerializer)?;
+        self.path.push(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), SerError> {
+        self.value(value)
+    }
+
+    fn end(self) -> std::result::Result<(), SerError> {
+        Ok(())
+    }
+}
+
+// a map key becomes part of a header name so it has to be a plain value
+struct KeySerializer;
+
+macro_rules! key_display {
+    ($($method:ident $ty:ty)*) => {
+        $(
+            fn $method(self, value: $ty) -> std::result::Result<String, SerError> {
+                Ok(value.to_string())
+            }
+        )*
+    };
+}
+
+macro_rules! key_unsupported {
+    ($($method:ident($($arg:ty),*) -> $ret:ty)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> std::result::Result<$ret, SerError> {
+                Err(ser::Error::custom("map keys have to be strings or numbers"))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = SerError;
+    type SerializeSeq = Impossible<String, SerError>;
+    type SerializeTuple = Impossible<String, SerError>;
+    type SerializeTupleStruct = Impossible<String, SerError>;
+    type SerializeTupleVariant = Impossible<String, SerError>;
+    type SerializeMap = Impossible<String, SerError>;
+    type SerializeStruct = Impossible<String, SerError>;
+    type SerializeStructVariant = Impossible<String, SerError>;
+
+    key_display! {
+        serialize_bool bool
+        serialize_i8 i8
+        serialize_i16 i16
+        serialize_i32 i32
+        serialize_i64 i64
+        serialize_i128 i128
+        serialize_u8 u8
+        serialize_u16 u16
+        serialize_u32 u32
+        serialize_u64 u64
+        serialize_u128 u128
+        serialize_f32 f32
+        serialize_f64 f64
+        serialize_char char
+        serialize_str &str
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> std::result::Result<String, SerError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> std::result::Result<String, SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> std::result::Result<String, SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> std::result::Result<String, SerError> {
+        Err(ser::Error::custom("map keys have to be strings or numbers"))
+    }
+
+    key_unsupported! {
+        serialize_bytes(&[u8]) -> String
+        serialize_none() -> String
+        serialize_unit() -> String
+        serialize_unit_struct(&'static str) -> String
+        serialize_seq(Option<usize>) -> Self::SerializeSeq
+        serialize_tuple(usize) -> Self::SerializeTuple
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant
+        serialize_map(Option<usize>) -> Self::SerializeMap
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant
+    }
+}
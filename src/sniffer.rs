@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use crate::encoding::Decoder;
+use crate::{ByteRecord, CsvParser, Dialect, RaggedRows, Result, Terminator};
+
+const DELIMITERS: [u8; 5] = [b',', b';', b'\t', b'|', b':'];
+const QUOTES: [u8; 2] = [b'"', b'\''];
+
+// what the sniffer found, `dialect` and `has_header` can be passed straight
+// to `CsvParser::dialect` and `CsvParser::with_header`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sniffed {
+    pub dialect: Dialect,
+    pub has_header: bool,
+    pub field_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sniffer {
+    sample_size: usize,
+}
+
+impl Default for Sniffer {
+    fn default() -> Self {
+        Sniffer {
+            sample_size: 64 * 1024,
+        }
+    }
+}
+
+impl Sniffer {
+    pub fn new() -> Self {
+        Sniffer::default()
+    }
+
+    // how many bytes from the start of the input are looked at
+    pub fn sample_size(mut self, size: usize) -> Self {
+        self.sample_size = size;
+        self
+    }
+
+    pub fn sniff<R: Read>(&self, reader: R) -> Result<Sniffed> {
+        let mut sample = Vec::new();
+        let limit = self.sample_size as u64;
+        Decoder::new(reader).take(limit).read_to_end(&mut sample)?;
+        // a cut off last record would look ragged
+        if sample.len() as u64 == limit {
+            if let Some(end) = sample.iter().rposition(|b| *b == b'\n' || *b == b'\r') {
+                sample.truncate(end + 1);
+            }
+        }
+
+        let quote = sniff_quote(&sample);
+        let terminator = sniff_terminator(&sample, quote);
+        let mut best: Option<(Dialect, Vec<ByteRecord>, usize, usize)> = None;
+        for delimiter in DELIMITERS {
+            let dialect = Dialect::new()
+                .delimiter(delimiter)
+                .quote(quote)
+                .terminator(terminator);
+            let records = read_sample(&sample, dialect);
+            let (field_count, matching) = most_common_len(&records);
+            if field_count < 2 {
+                continue;
+            }
+            // the delimiter that splits the most rows the same way wins, ties
+            // go to the one that gives more fields and then to the list order
+            let better = match &best {
+                Some((_, _, count, best_matching)) => {
+                    matching > *best_matching
+                        || (matching == *best_matching && field_count > *count)
+                }
+                None => true,
+            };
+            if better {
+                best = Some((dialect, records, field_count, matching));
+            }
+        }
+
+        let (dialect, records, field_count) = match best {
+            Some((dialect, records, field_count, _)) => (dialect, records, field_count),
+            None => {
+                let dialect = Dialect::new().quote(quote).terminator(terminator);
+                let records = read_sample(&sample, dialect);
+                let (field_count, _) = most_common_len(&records);
+                (dialect, records, field_count)
+            }
+        };
+        Ok(Sniffed {
+            dialect,
+            has_header: sniff_header(&records, field_count),
+            field_count,
+        })
+    }
+}
+
+fn read_sample(sample: &[u8], dialect: Dialect) -> Vec<ByteRecord> {
+    let mut parser = CsvParser::new(io::Cursor::new(sample))
+        .dialect(dialect)
+        .ragged_rows(RaggedRows::Accept)
+        .recover(true);
+    parser
+        .byte_records()
+        .map_while(|record| record.ok())
+        .filter(|record| !(record.len() == 1 && record.as_slice().is_empty()))
+        .collect()
+}
+
+fn most_common_len(records: &[ByteRecord]) -> (usize, usize) {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for record in records {
+        *counts.entry(record.len()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(len, count)| (*count, *len))
+        .unwrap_or_default()
+}
+
+// the quote char is the one that most often opens a field
+fn sniff_quote(sample: &[u8]) -> u8 {
+    let mut best = (QUOTES[0], 0);
+    for quote in QUOTES {
+        let opens = sample
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| {
+                **b == quote
+                    && match i.checked_sub(1).map(|prev| sample[prev]) {
+                        None => true,
+                        Some(prev) => prev == b'\n' || prev == b'\r' || DELIMITERS.contains(&prev),
+                    }
+            })
+            .count();
+        if opens > best.1 {
+            best = (quote, opens);
+        }
+    }
+    best.0
+}
+
+// files that only use `\n` get it as their terminator, anything with a `\r`
+// keeps the default that accepts all three line breaks
+fn sniff_terminator(sample: &[u8], quote: u8) -> Terminator {
+    let mut quoted = false;
+    let mut lf = false;
+    for b in sample {
+        match *b {
+            b if b == quote => quoted = !quoted,
+            b'\r' if !quoted => return Terminator::CRLF,
+            b'\n' if !quoted => lf = true,
+            _ => {}
+        }
+    }
+    match lf {
+        true => Terminator::Any(b'\n'),
+        false => Terminator::CRLF,
+    }
+}
+
+// the first record is a header when it is shaped unlike the rest, each
+// column votes on its own: a numeric column with a text name or a column of
+// one fixed width with a name of another width both say header
+fn sniff_header(records: &[ByteRecord], field_count: usize) -> bool {
+    let (first, rest) = match records.split_first() {
+        Some((first, rest)) if !rest.is_empty() => (first, rest),
+        _ => return false,
+    };
+    if first.len() != field_count {
+        return false;
+    }
+    let mut names = Vec::new();
+    for name in first.iter() {
+        if name.is_empty() || names.contains(&name) {
+            return false;
+        }
+        names.push(name);
+    }
+
+    let mut votes = 0i64;
+    for (i, name) in first.iter().enumerate() {
+        let column: Vec<&[u8]> = rest
+            .iter()
+            .filter_map(|record| record.get(i))
+            .filter(|field| !field.is_empty())
+            .collect();
+        if column.is_empty() {
+            continue;
+        }
+        if column.iter().all(|field| is_number(field)) {
+            votes += match is_number(name) {
+                true => -1,
+                false => 1,
+            };
+            continue;
+        }
+        let width = column[0].len();
+        if column.iter().all(|field| field.len() == width) {
+            votes += match name.len() == width {
+                true => -1,
+                false => 1,
+            };
+        }
+    }
+    votes > 0
+}
+
+fn is_number(field: &[u8]) -> bool {
+    std::str::from_utf8(field).is_ok_and(|text| {
+        text.bytes().any(|b| b.is_ascii_digit()) && text.trim().parse::<f64>().is_ok()
+    })
+}
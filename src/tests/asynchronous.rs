@@ -0,0 +1,115 @@
+use std::future::poll_fn;
+use std::pin::Pin;
+
+use futures_core::Stream;
+use proptest::prelude::*;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+
+use super::debug;
+use crate::{AsyncCsvParser, CsvError, CsvParser, Dialect, Record, Result};
+
+async fn collect<R: AsyncRead + Unpin>(mut parser: AsyncCsvParser<R>) -> Vec<Result<Record>> {
+    let mut records = Vec::new();
+    while let Some(record) = poll_fn(|cx| Pin::new(&mut parser).poll_next(cx)).await {
+        records.push(record);
+    }
+    records
+}
+
+// writes `data` into one end of a duplex stream `size` bytes at a time while
+// the other end is parsed
+async fn parse_duplex(
+    data: Vec<u8>,
+    size: usize,
+    f: impl FnOnce(AsyncCsvParser<tokio::io::DuplexStream>) -> AsyncCsvParser<tokio::io::DuplexStream>,
+) -> Vec<String> {
+    let (mut writer, reader) = tokio::io::duplex(size);
+    let write = async move {
+        for chunk in data.chunks(size) {
+            // the parser stops reading after an error
+            if writer.write_all(chunk).await.is_err() {
+                break;
+            }
+        }
+    };
+    let (_, records) = tokio::join!(write, collect(f(AsyncCsvParser::new(reader))));
+    debug(records)
+}
+
+#[tokio::test]
+async fn async_same_records_as_parser() {
+    let str = "\u{feff}id,text\r\n1,\"two\r\nlines, \"\"quoted\"\"\"\r\n2,é\n3,\"\"";
+    let expected: Vec<String> = CsvParser::from_string(String::from(str))
+        .with_header(true)
+        .map(|record| format!("{record:?}"))
+        .collect();
+    assert_eq!(expected.len(), 3);
+    for size in [1, 2, 5, 64] {
+        let found = parse_duplex(str.as_bytes().to_vec(), size, |parser| {
+            parser.with_header(true)
+        })
+        .await;
+        assert_eq!(expected, found, "{size}");
+    }
+}
+
+#[tokio::test]
+async fn async_headers_and_errors() {
+    let (mut writer, reader) = tokio::io::duplex(4);
+    let write = async move {
+        let _ = writer.write_all(b"a,b\n1,2\n3\n4,5\n").await;
+    };
+    let read = async {
+        let mut parser = AsyncCsvParser::new(reader).with_header(true);
+        match parser.headers().await {
+            Ok(Some(headers)) => assert_eq!(headers, ["a", "b"]),
+            other => panic!("{other:?}"),
+        }
+        let mut records: Vec<Result<_>> = Vec::new();
+        while let Some(record) = poll_fn(|cx| Pin::new(&mut parser).poll_next(cx)).await {
+            records.push(record);
+        }
+        records
+    };
+    let (_, records) = tokio::join!(write, read);
+    assert_eq!(records.len(), 2);
+    match &records[0] {
+        Ok(record) => assert_eq!(record.get("b"), Some("2")),
+        Err(err) => panic!("{err:?}"),
+    }
+    assert!(matches!(
+        records[1],
+        Err(CsvError::UnequalLengths {
+            expected: 2,
+            found: 1,
+            ..
+        })
+    ));
+}
+
+proptest! {
+    #[test]
+    fn async_same_result_for_any_read_size(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", ",", "\"", "\\", "\r", "\n", "\u{feff}"]),
+            0..40,
+        ),
+        size in 1usize..8,
+        escape in prop::option::of(Just(b'\\')),
+        header in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let dialect = Dialect::new().escape(escape);
+        let expected: Vec<String> = CsvParser::from_string(str.clone())
+            .dialect(dialect)
+            .with_header(header)
+            .flexible(true)
+            .map(|record| format!("{record:?}"))
+            .collect();
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let found = runtime.block_on(parse_duplex(str.into_bytes(), size, |parser| {
+            parser.dialect(dialect).with_header(header).flexible(true)
+        }));
+        prop_assert_eq!(expected, found);
+    }
+}
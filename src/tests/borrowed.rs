@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+
+use proptest::prelude::*;
+
+use crate::{
+    BorrowedRecord, CsvError, CsvParser, Dialect, Escape, Record, SliceParser, Terminator,
+};
+
+#[test]
+fn borrowed_fields_point_into_input() {
+    let str = "id,name\r\n1,\"plain, quoted\"\r\n2,\"say \"\"hi\"\"\"\r\n";
+    let mut parser = SliceParser::new(str).with_header(true);
+    let records = match parser.by_ref().collect::<Result<Vec<_>, _>>() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0], vec!["1", "plain, quoted"]);
+    assert_eq!(records[1], vec!["2", "say \"hi\""]);
+    assert!(matches!(records[0].fields()[1], Cow::Borrowed(_)));
+    assert!(matches!(records[1].fields()[0], Cow::Borrowed(_)));
+    assert!(matches!(records[1].fields()[1], Cow::Owned(_)));
+    assert_eq!(records[1].position().line(), 3);
+    let headers = match parser.headers() {
+        Ok(Some(headers)) => headers,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(headers, ["id", "name"]);
+}
+
+#[test]
+fn borrowed_trim_and_errors() {
+    let bytes: &[u8] = b" a , b \nc,d\xff\n";
+    let mut parser = SliceParser::new(bytes)
+        .trim_space(true)
+        .dialect(Dialect::new().terminator(Terminator::Any(b'\n')));
+    match parser.next() {
+        Some(Ok(record)) => {
+            assert_eq!(record, vec!["a", "b"]);
+            assert!(record
+                .fields()
+                .iter()
+                .all(|field| matches!(field, Cow::Borrowed(_))));
+        }
+        other => panic!("{other:?}"),
+    }
+    match parser.next() {
+        Some(Err(CsvError::InvalidUtf8 { field, position })) => {
+            assert_eq!(field, 2);
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 2);
+            assert_eq!(position.column(), 4);
+            assert_eq!(position.byte(), 11);
+        }
+        other => panic!("{other:?}"),
+    }
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn borrowed_read_record_reuses_record() {
+    let str = "a,b\n\"c\"\"\",d\ne,\"f\"\n";
+    let mut parser = SliceParser::new(str);
+    let mut record = BorrowedRecord::default();
+    let mut found = Vec::new();
+    loop {
+        match parser.read_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => panic!("{err:?}"),
+        }
+        let borrowed = record
+            .fields()
+            .iter()
+            .map(|field| matches!(field, Cow::Borrowed(_)))
+            .collect::<Vec<_>>();
+        found.push((Record::from(record.clone()), borrowed));
+    }
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[1].0, vec!["c\"", "d"]);
+    assert_eq!(found[1].1, [false, true]);
+    assert_eq!(found[2].0, vec!["e", "f"]);
+    assert_eq!(found[2].1, [true, true]);
+    assert!(record.is_empty());
+}
+
+proptest! {
+    #[test]
+    fn borrowed_same_records_as_parser(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", " ", ",", "\"", "\\", "\r", "\n", "#"]),
+            0..40,
+        ),
+        escape in prop::option::of(Just(b'\\')),
+        escaping in prop::sample::select(vec![Escape::Doubled, Escape::Backslash, Escape::None]),
+        quoting in any::<bool>(),
+        trim in any::<bool>(),
+        comment in prop::option::of(Just(b'#')),
+        skip_rows in 0u64..3,
+        blank in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let dialect = Dialect::new()
+            .escape(escape)
+            .escaping(escaping)
+            .quoting(quoting);
+        let parser = CsvParser::from_string(str.clone())
+            .dialect(dialect)
+            .trim_space(trim)
+            .comment(comment)
+            .skip_rows(skip_rows)
+            .skip_blank_lines(blank)
+            .flexible(true);
+        let borrowed = SliceParser::new(&str)
+            .dialect(dialect)
+            .trim_space(trim)
+            .comment(comment)
+            .skip_rows(skip_rows)
+            .skip_blank_lines(blank)
+            .flexible(true)
+            .map(|record| record.map(Record::from));
+        let expected: Vec<String> = parser.map(|record| format!("{record:?}")).collect();
+        let found: Vec<String> = borrowed.map(|record| format!("{record:?}")).collect();
+        prop_assert_eq!(expected, found);
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use super::temp_file;
+use crate::{Compression, CsvParser, Decompress, ParallelParser};
+
+const DATA: &str = "id\ttext\r\n1\t\"a\r\nb\"\r\n2\té\r\n";
+
+#[test]
+fn compression_detect() {
+    assert_eq!(
+        Compression::detect(b"\x1F\x8B\x08"),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(
+        Compression::detect(b"\x28\xB5\x2F\xFD"),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(Compression::detect(b"BZh91AY&SY"), Some(Compression::Bzip2));
+    assert_eq!(Compression::detect(b"BZh9"), None);
+    assert_eq!(Compression::detect(b"BZh,col\n1,2\n"), None);
+    assert_eq!(Compression::detect(b"BZ"), None);
+    assert_eq!(Compression::for_label("GZ"), Some(Compression::Gzip));
+    assert_eq!(
+        Compression::from_path(&PathBuf::from("a.csv.zst")),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(Compression::from_path(&PathBuf::from("a.csv")), None);
+
+    let mut plain = String::new();
+    match Decompress::new(DATA.as_bytes()).map(|mut reader| reader.read_to_string(&mut plain)) {
+        Ok(Ok(_)) => assert_eq!(plain, DATA),
+        other => panic!("{other:?}"),
+    }
+    // a compressed file can not be split into chunks
+    let path = temp_file("split.csv", b"\x1F\x8B\x08\x00");
+    match ParallelParser::from_path(&path) {
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+        Ok(_) => panic!("a gzip file should not be split"),
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn compression_plain_file_that_starts_like_bzip2() {
+    let path = temp_file("bzh.csv", b"BZh,col\n1,2\n");
+    let records: Vec<_> = match CsvParser::from_path(&path) {
+        Ok(parser) => parser.with_header(true).collect(),
+        Err(err) => panic!("{err:?}"),
+    };
+    match records.as_slice() {
+        [Ok(record)] => assert_eq!(record.get("BZh"), Some("1")),
+        other => panic!("{other:?}"),
+    }
+    match ParallelParser::from_path(&path) {
+        Ok(parser) => assert_eq!(parser.with_header(true).count(), 1),
+        Err(err) => panic!("{err:?}"),
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn compression_needs_feature() {
+    match Decompress::new(&b"\x1F\x8B\x08\x00"[..]) {
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::Unsupported),
+        Ok(reader) => panic!("{reader:?}"),
+    }
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd", feature = "bzip2"))]
+#[test]
+fn compression_same_records_as_plain() {
+    use std::io::Write;
+
+    use super::debug;
+    use crate::Sniffer;
+
+    fn records<R: Read>(parser: io::Result<CsvParser<R>>) -> Vec<String> {
+        match parser {
+            Ok(parser) => debug(parser.with_header(true)),
+            Err(err) => panic!("{err:?}"),
+        }
+    }
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+    let compressed = (|| -> io::Result<_> {
+        gzip.write_all(DATA.as_bytes())?;
+        bzip2.write_all(DATA.as_bytes())?;
+        Ok([
+            ("data.tsv.gz", gzip.finish()?),
+            ("data.tsv.zst", zstd::encode_all(DATA.as_bytes(), 0)?),
+            // found by the magic bytes alone
+            ("data_bz2.tsv", bzip2.finish()?),
+        ])
+    })();
+    let compressed = match compressed {
+        Ok(compressed) => compressed,
+        Err(err) => panic!("{err:?}"),
+    };
+    let plain = temp_file("data.tsv", DATA.as_bytes());
+    let expected = records(CsvParser::from_path(&plain));
+    assert_eq!(expected.len(), 2);
+    for (name, data) in compressed {
+        let path = temp_file(name, &data);
+        assert_eq!(expected, records(CsvParser::from_path(&path)), "{name}");
+        assert_eq!(
+            expected,
+            records(CsvParser::from_path_sniffed(&path)),
+            "{name}"
+        );
+        let file = match Decompress::from_path(&path) {
+            Ok(file) => file,
+            Err(err) => panic!("{err:?}"),
+        };
+        match Sniffer::new().sniff(file) {
+            Ok(sniffed) => assert_eq!(sniffed.dialect.delimiter, b'\t', "{name}"),
+            Err(err) => panic!("{err:?}"),
+        }
+        let _ = std::fs::remove_file(path);
+    }
+    let _ = std::fs::remove_file(plain);
+}
@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+use crate::{CsvError, CsvParser};
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Kind {
+    Admin,
+    User,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Person {
+    name: String,
+    age: u32,
+    score: f64,
+    active: bool,
+    nick: Option<String>,
+    kind: Kind,
+}
+
+#[test]
+fn deserialize_by_header() {
+    let str = String::from(
+        "kind,active,name,age,nick,score\r\nAdmin,true,ann,30,,1.5\r\nUser,false,bob,7,bobby,-2\r\n",
+    );
+    let mut parser = CsvParser::from_string(str).with_header(true);
+    let people = match parser
+        .deserialize::<Person>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(people) => people,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(
+        people,
+        vec![
+            Person {
+                name: String::from("ann"),
+                age: 30,
+                score: 1.5,
+                active: true,
+                nick: None,
+                kind: Kind::Admin,
+            },
+            Person {
+                name: String::from("bob"),
+                age: 7,
+                score: -2.0,
+                active: false,
+                nick: Some(String::from("bobby")),
+                kind: Kind::User,
+            },
+        ]
+    );
+}
+
+#[test]
+fn deserialize_by_position() {
+    let str = String::from("ann,30,1.5,true,,Admin\r\n");
+    let mut parser = CsvParser::from_string(str);
+    let people = match parser
+        .deserialize::<Person>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(people) => people,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(people[0].name, "ann");
+    assert_eq!(people[0].nick, None);
+    assert_eq!(people[0].kind, Kind::Admin);
+
+    let str = String::from("a,1,\r\nb,2,3\r\n");
+    let mut parser = CsvParser::from_string(str);
+    let rows = match parser
+        .deserialize::<(String, i64, Option<u8>)>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(rows) => rows,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(
+        rows,
+        vec![
+            (String::from("a"), 1, None),
+            (String::from("b"), 2, Some(3))
+        ]
+    );
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Address {
+    city: String,
+    zip: u32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Customer {
+    name: String,
+    #[serde(flatten)]
+    address: Address,
+}
+
+#[test]
+fn deserialize_flatten() {
+    let str = String::from("name,city,zip\r\nann,Oslo,150\r\n");
+    let mut parser = CsvParser::from_string(str).with_header(true);
+    let customers = match parser
+        .deserialize::<Customer>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(customers) => customers,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(
+        customers,
+        vec![Customer {
+            name: String::from("ann"),
+            address: Address {
+                city: String::from("Oslo"),
+                zip: 150,
+            },
+        }]
+    );
+}
+
+#[test]
+fn deserialize_error_names_column_and_line() {
+    let str = String::from(
+        "name,age,score,active,nick,kind\r\nann,30,1,true,,Admin\r\nbob,old,1,true,,User\r\ncy,3,1,true,,User\r\n",
+    );
+    let mut parser = CsvParser::from_string(str).with_header(true);
+    let people = parser.deserialize::<Person>().collect::<Vec<_>>();
+    assert_eq!(people.len(), 3);
+    assert!(people[0].is_ok());
+    assert!(people[2].is_ok());
+    match &people[1] {
+        Err(err @ CsvError::Deserialize { field, header, .. }) => {
+            assert_eq!(*field, Some(2));
+            assert_eq!(header.as_deref(), Some("age"));
+            assert_eq!(err.position().line(), 3);
+            assert!(err.to_string().contains("field 2 (\"age\")"));
+        }
+        other => panic!("{other:?}"),
+    }
+}
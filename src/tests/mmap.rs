@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+
+use super::temp_file;
+use crate::{CsvParser, MappedFile};
+
+#[test]
+fn mmap_same_records_as_parser() {
+    let path = temp_file(
+        "mmap.csv",
+        "\u{feff}id,text\r\n1,\"a\r\nb\"\r\n2,é\r\n".as_bytes(),
+    );
+    let mapped = match CsvParser::from_path_mmap(&path) {
+        Ok(parser) => parser.with_header(true),
+        Err(err) => panic!("{err:?}"),
+    };
+    let buffered = match CsvParser::from_path(&path) {
+        Ok(parser) => parser.with_header(true),
+        Err(err) => panic!("{err:?}"),
+    };
+    let expected: Vec<String> = buffered.map(|record| format!("{record:?}")).collect();
+    let found: Vec<String> = mapped.map(|record| format!("{record:?}")).collect();
+    assert_eq!(expected.len(), 2);
+    assert_eq!(expected, found);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn mmap_borrowed_records() {
+    let path = temp_file("mmap_borrowed.csv", b"a,\"b, c\"\r\n1,2\r\n");
+    let file = match MappedFile::open(&path) {
+        Ok(file) => file,
+        Err(err) => panic!("{err:?}"),
+    };
+    let records = match file.records().collect::<Result<Vec<_>, _>>() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(records[0], vec!["a", "b, c"]);
+    assert_eq!(records[1], vec!["1", "2"]);
+    assert!(records
+        .iter()
+        .flat_map(|record| record.fields())
+        .all(|field| matches!(field, Cow::Borrowed(_))));
+
+    let empty = temp_file("mmap_empty.csv", b"");
+    match MappedFile::open(&empty) {
+        Ok(file) => assert_eq!(file.records().count(), 0),
+        Err(err) => panic!("{err:?}"),
+    }
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(empty);
+}
+
+#[test]
+fn mmap_dialect_from_extension() {
+    let path = temp_file("mmap.tsv", b"a\tb,c\r\n1\t2\r\n");
+    let file = match MappedFile::open(&path) {
+        Ok(file) => file,
+        Err(err) => panic!("{err:?}"),
+    };
+    let records = match file.records().collect::<Result<Vec<_>, _>>() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(records[0], vec!["a", "b,c"]);
+    assert_eq!(records[1], vec!["1", "2"]);
+    match CsvParser::from_path_mmap(&path).map(|mut parser| parser.parse()) {
+        Ok(Ok(tokens)) => assert_eq!(tokens[0], vec!["a", "b,c"]),
+        other => panic!("{other:?}"),
+    }
+    let _ = std::fs::remove_file(path);
+}
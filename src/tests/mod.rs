@@ -1,4 +1,7 @@
-use crate::CsvParser;
+use crate::{
+    ByteRecord, CsvError, CsvParser, Dialect, Encoding, Escape, RaggedRows, Record, Terminator,
+    Utf8Mode,
+};
 
 #[test]
 fn parser_base() {
@@ -32,7 +35,7 @@ fn parser_base() {
 
     println!("token: {tokens:?}");
     for (token, exp) in tokens.iter().zip(expect) {
-        assert_eq!(token.value, exp);
+        assert_eq!(*token, exp);
     }
 }
 
@@ -71,7 +74,7 @@ fn parser_trim_fail() {
             .iter()
             .map(|x| x.trim().to_owned())
             .collect::<Vec<String>>();
-        assert_ne!(token.value, trimmed_exp);
+        assert_ne!(*token, trimmed_exp);
     }
 }
 
@@ -110,7 +113,7 @@ fn parser_trim() {
             .iter()
             .map(|x| x.trim().to_owned())
             .collect::<Vec<String>>();
-        assert_eq!(token.value, trimmed_exp);
+        assert_eq!(*token, trimmed_exp);
     }
 }
 
@@ -155,7 +158,7 @@ fn parser_all_whitespace_fail() {
                 }
             })
             .collect::<Vec<String>>();
-        assert_ne!(token.value, trimmed_exp);
+        assert_ne!(*token, trimmed_exp);
     }
 }
 
@@ -200,7 +203,7 @@ fn parser_all_whitespace() {
                 }
             })
             .collect::<Vec<String>>();
-        assert_eq!(token.value, trimmed_exp);
+        assert_eq!(*token, trimmed_exp);
     }
 }
 
@@ -249,7 +252,7 @@ fn parser_escaped() {
                 }
             })
             .collect::<Vec<String>>();
-        assert_eq!(token.value, new_exp);
+        assert_eq!(*token, new_exp);
     }
 }
 
@@ -304,7 +307,7 @@ fn parser_escaped_has_newline() {
                 }
             })
             .collect::<Vec<String>>();
-        assert_eq!(token.value, new_exp);
+        assert_eq!(*token, new_exp);
     }
 }
 
@@ -360,7 +363,7 @@ fn parser_escaped_fail() {
                 }
             })
             .collect::<Vec<String>>();
-        assert_ne!(token.value, new_exp);
+        assert_ne!(*token, new_exp);
     }
 }
 
@@ -385,9 +388,9 @@ fn parser_irregular_rows() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 3);
-    assert_eq!(tokens[0].value, vec!["1", "2", "3"]);
-    assert_eq!(tokens[1].value, vec!["4", "5"]);
-    assert_eq!(tokens[2].value, vec!["6"]);
+    assert_eq!(tokens[0], vec!["1", "2", "3"]);
+    assert_eq!(tokens[1], vec!["4", "5"]);
+    assert_eq!(tokens[2], vec!["6"]);
 }
 
 #[test]
@@ -399,8 +402,8 @@ fn parser_empty_fields() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 2);
-    assert_eq!(tokens[0].value, vec!["", "", "", ""]);
-    assert_eq!(tokens[1].value, vec!["", "", "", ""]);
+    assert_eq!(tokens[0], vec!["", "", "", ""]);
+    assert_eq!(tokens[1], vec!["", "", "", ""]);
 }
 
 #[test]
@@ -412,8 +415,8 @@ fn parser_trailing_newline() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 2);
-    assert_eq!(tokens[0].value, vec!["1", "2", "3"]);
-    assert_eq!(tokens[1].value, vec!["4", "5", "6"]);
+    assert_eq!(tokens[0], vec!["1", "2", "3"]);
+    assert_eq!(tokens[1], vec!["4", "5", "6"]);
 }
 
 #[test]
@@ -425,7 +428,7 @@ fn parser_quoted_fields_with_commas() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 1);
-    assert_eq!(tokens[0].value, vec!["field, with, commas", "2", "3"]);
+    assert_eq!(tokens[0], vec!["field, with, commas", "2", "3"]);
 }
 
 #[test]
@@ -437,7 +440,7 @@ fn parser_single_field() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 1);
-    assert_eq!(tokens[0].value, vec!["singlefield"]);
+    assert_eq!(tokens[0], vec!["singlefield"]);
 }
 
 #[test]
@@ -449,7 +452,7 @@ fn parser_quoted_fields_with_newline() {
         Err(err) => panic!("{err:?}"),
     };
     assert_eq!(tokens.len(), 1);
-    assert_eq!(tokens[0].value, vec!["field\nwith\nnewlines", "2", "3"]);
+    assert_eq!(tokens[0], vec!["field\nwith\nnewlines", "2", "3"]);
 }
 
 #[test]
@@ -462,7 +465,997 @@ fn parser_escaped_quotes() {
     };
     assert_eq!(tokens.len(), 1);
     assert_eq!(
-        tokens[0].value,
+        tokens[0],
         vec!["field with \"escaped quotes\"", "2", "3"]
     );
 }
+
+#[test]
+fn parser_escaped_backslash() {
+    let str = "\"a\\\"b\",c\\,d,\\\\\n\"x\\\\y\",tab\\there\\\nnext,\\\"z\n";
+    let mut parser = CsvParser::from_string(str.to_string()).escaping(Escape::Backslash);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["a\"b", "c,d", "\\"]);
+    assert_eq!(tokens[1], vec!["x\\y", "tab\there\nnext", "\"z"]);
+}
+
+#[test]
+fn parser_escaped_backslash_single_quote() {
+    let str = "'it\\'s',2\n'a''b',3";
+    let dialect = Dialect::new().quote(b'\'').escaping(Escape::Backslash);
+    let mut parser = CsvParser::from_string(str.to_string()).dialect(dialect);
+    assert!(matches!(parser.next(), Some(Ok(token)) if token == vec!["it's", "2"]));
+    // doubled quotes are not an escape with backslashes
+    assert!(matches!(
+        parser.next(),
+        Some(Err(CsvError::UnexpectedQuote { .. }))
+    ));
+}
+
+#[test]
+fn parser_escaped_backslash_custom_byte() {
+    let str = "\"a^\"b\",c^,d,back\\slash\ntab^there,^^\n";
+    let dialect = Dialect::new()
+        .escape(Some(b'^'))
+        .escaping(Escape::Backslash);
+    let mut parser = CsvParser::from_string(str.to_string())
+        .dialect(dialect)
+        .flexible(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["a\"b", "c,d", "back\\slash"]);
+    assert_eq!(tokens[1], vec!["tab\there", "^"]);
+}
+
+#[test]
+fn parser_escaped_none() {
+    let str = "\"a,b\",c\\d\n\"x\"\"y\",z";
+    let mut parser = CsvParser::from_string(str.to_string()).escaping(Escape::None);
+    assert!(matches!(parser.next(), Some(Ok(token)) if token == vec!["a,b", "c\\d"]));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(CsvError::UnexpectedQuote { .. }))
+    ));
+}
+
+#[test]
+fn parser_quoting_disabled() {
+    let str = "\"a,b\",\"c\"\"d\"\nx\"y,z,\"";
+    let mut parser = CsvParser::from_string(str.to_string()).quoting(false);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["\"a", "b\"", "\"c\"\"d\""]);
+    assert_eq!(tokens[1], vec!["x\"y", "z", "\""]);
+}
+
+#[test]
+fn parser_iter_records() {
+    let str = "1,2,3\n4,5,6\n7,8,9";
+    let mut parser = CsvParser::from_string(str.to_string());
+
+    let first = match parser.next() {
+        Some(Ok(token)) => token,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(first, vec!["1", "2", "3"]);
+    assert_eq!(first.position().line(), 1);
+
+    let rest = parser
+        .map(|token| match token {
+            Ok(token) => Vec::<String>::from(token),
+            Err(err) => panic!("{err:?}"),
+        })
+        .collect::<Vec<Vec<String>>>();
+    assert_eq!(rest, vec![vec!["4", "5", "6"], vec!["7", "8", "9"]]);
+}
+
+#[test]
+fn parser_iter_stops_after_error() {
+    let str = "1,2,3\n4,5\n6,7,8";
+    let mut parser = CsvParser::from_string(str.to_string());
+
+    assert!(matches!(parser.next(), Some(Ok(_))));
+    assert!(matches!(parser.next(), Some(Err(_))));
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn parser_escaped_trailing_empty_field() {
+    let str = "\"a\",\n\"b\",";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["a", ""]);
+    assert_eq!(tokens[1], vec!["b", ""]);
+}
+
+#[test]
+fn parser_header() {
+    let str = "name,price\napple,1.5\npear,2";
+    let mut parser = CsvParser::from_string(str.to_string()).with_header(true);
+
+    match parser.headers() {
+        Ok(Some(headers)) => assert_eq!(headers, ["name", "price"]),
+        other => panic!("{other:?}"),
+    }
+
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].get("name"), Some("apple"));
+    assert_eq!(tokens[0].get("price"), Some("1.5"));
+    assert_eq!(tokens[1].get("price"), Some("2"));
+    assert_eq!(tokens[1].get("weight"), None);
+}
+
+#[test]
+fn parser_no_header() {
+    let str = "name,price\napple,1.5";
+    let mut parser = CsvParser::from_string(str.to_string());
+
+    assert!(matches!(parser.headers(), Ok(None)));
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].get("name"), None);
+}
+
+#[test]
+fn parser_header_duplicate() {
+    let str = "name,price,name\napple,1.5,pear";
+    let mut parser = CsvParser::from_string(str.to_string()).with_header(true);
+    let err = match parser.parse() {
+        Ok(tokens) => panic!("{tokens:?}"),
+        Err(err) => err,
+    };
+    assert!(err.to_string().contains("duplicate header \"name\" in columns 1 and 3"));
+}
+
+#[test]
+fn parser_header_empty() {
+    let str = "name,,price\napple,1,1.5";
+    let mut parser = CsvParser::from_string(str.to_string()).with_header(true);
+    let err = match parser.parse() {
+        Ok(tokens) => panic!("{tokens:?}"),
+        Err(err) => err,
+    };
+    assert!(err.to_string().contains("header in column 2 is empty"));
+}
+
+#[test]
+fn parser_record_accessors() {
+    let str = "é,b,\"c\"\"\"\nd,e,f\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+
+    let record = &tokens[0];
+    assert_eq!(record.len(), 3);
+    assert!(!record.is_empty());
+    assert_eq!(record.get(0), Some("é"));
+    assert_eq!(record.get(2), Some("c\""));
+    assert_eq!(record.get(3), None);
+    assert_eq!(record.iter().collect::<Vec<&str>>(), vec!["é", "b", "c\""]);
+    assert_eq!(record.iter().rev().next(), Some("c\""));
+    assert_eq!(record.as_slice(), "ébc\"");
+    assert_eq!(record.position().line(), 1);
+    assert_eq!(record.position().byte(), 0);
+
+    let record = tokens[1].clone();
+    assert_eq!(record.position().line(), 2);
+    assert_eq!(record.position().byte(), 11);
+    assert_eq!(Vec::<String>::from(record), vec!["d", "e", "f"]);
+}
+
+#[test]
+fn parser_record_from_vec() {
+    let mut record = Record::from(vec!["a", "", "c"]);
+    assert_eq!(record.len(), 3);
+    assert_eq!(record.get(1), Some(""));
+    record.push_field("d");
+    assert_eq!(record, vec!["a", "", "c", "d"]);
+    record.clear();
+    assert!(record.is_empty());
+}
+
+#[test]
+fn parser_dialect_delimiter() {
+    let inputs = [
+        ("1;2;\"3;4\"\na;b;c", b';'),
+        ("1|2|\"3|4\"\na|b|c", b'|'),
+        ("1\t2\t\"3\t4\"\na\tb\tc", b'\t'),
+    ];
+    for (str, delimiter) in inputs {
+        let mut parser = CsvParser::from_string(str.to_string()).delimiter(delimiter);
+        let tokens = match parser.parse() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("{err:?}"),
+        };
+        let sep = (delimiter as char).to_string();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0], vec!["1", "2", &format!("3{sep}4")]);
+        assert_eq!(tokens[1], vec!["a", "b", "c"]);
+    }
+}
+
+#[test]
+fn parser_dialect_quote_and_escape() {
+    let str = "'it''s',2\n'a\\'b',3";
+    let dialect = Dialect::new().quote(b'\'').escape(Some(b'\\'));
+    let mut parser = CsvParser::from_string(str.to_string()).dialect(dialect);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["it's", "2"]);
+    assert_eq!(tokens[1], vec!["a'b", "3"]);
+}
+
+#[test]
+fn parser_dialect_terminator() {
+    let str = "1,2\n3~4,5~";
+    let mut parser = CsvParser::from_string(str.to_string()).terminator(Terminator::Any(b'~'));
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["1", "2\n3"]);
+    assert_eq!(tokens[1], vec!["4", "5"]);
+}
+
+#[test]
+fn parser_dialect_default_is_rfc4180() {
+    let dialect = Dialect::default();
+    assert_eq!(dialect.delimiter, b',');
+    assert_eq!(dialect.quote, b'"');
+    assert_eq!(dialect.escape, None);
+    assert_eq!(dialect.escaping, Escape::Doubled);
+    assert!(dialect.quoting);
+    assert_eq!(dialect.terminator, Terminator::CRLF);
+}
+
+#[test]
+fn parser_error_unequal_lengths() {
+    let str = "1,2,3\n4,5,6\n7,8";
+    let mut parser = CsvParser::from_string(str.to_string());
+    match parser.parse() {
+        Err(CsvError::UnequalLengths {
+            expected,
+            found,
+            position,
+        }) => {
+            assert_eq!(expected, 3);
+            assert_eq!(found, 2);
+            assert_eq!(position.record(), 3);
+            assert_eq!(position.line(), 3);
+            assert_eq!(position.column(), 1);
+            assert_eq!(position.byte(), 12);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_error_unexpected_quote() {
+    let str = "1,2,3\n4,a\"b,6";
+    let mut parser = CsvParser::from_string(str.to_string());
+    match parser.parse() {
+        Err(CsvError::UnexpectedQuote { position }) => {
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 2);
+            assert_eq!(position.column(), 4);
+            assert_eq!(position.byte(), 9);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_error_unterminated_quote() {
+    let str = "1,2\n\"multi\nline,3\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    match parser.parse() {
+        Err(CsvError::UnterminatedQuote { position }) => {
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 2);
+            assert_eq!(position.column(), 1);
+            assert_eq!(position.byte(), 4);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_error_line_after_multiline_field() {
+    let str = "\"a\nb\nc\",1\nx\"y,2";
+    let mut parser = CsvParser::from_string(str.to_string());
+    match parser.parse() {
+        Err(CsvError::UnexpectedQuote { position }) => {
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 4);
+            assert_eq!(position.column(), 2);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_error_invalid_utf8() {
+    let bytes = b"ab,c\nd,\xff".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    match parser.parse() {
+        Err(CsvError::InvalidUtf8 { field, position }) => {
+            assert_eq!(field, 2);
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 2);
+            assert_eq!(position.column(), 3);
+            assert_eq!(position.byte(), 7);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_error_invalid_utf8_position() {
+    let bytes = b"ab,c\nd,\"x\n\xff\"".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    match parser.parse() {
+        Err(CsvError::InvalidUtf8 { field, position }) => {
+            assert_eq!(field, 2);
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 3);
+            assert_eq!(position.column(), 1);
+            assert_eq!(position.byte(), 10);
+        }
+        other => panic!("{other:?}"),
+    }
+
+    let bytes = b"ab,c\n  \xe9a,\"\xc3\xa9\xff\"".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).trim_space(true);
+    let mut record = crate::ByteRecord::new();
+    match parser.read_byte_record(&mut record) {
+        Ok(true) => {}
+        other => panic!("{other:?}"),
+    }
+    match parser.read_byte_record(&mut record) {
+        Ok(true) => {}
+        other => panic!("{other:?}"),
+    }
+    for (i, column, byte) in [(0, 3, 7), (1, 8, 13)] {
+        match record.get_str(i) {
+            Some(Err(CsvError::InvalidUtf8 { position, .. })) => {
+                assert_eq!(position.line(), 2);
+                assert_eq!(position.column(), column);
+                assert_eq!(position.byte(), byte);
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+}
+
+#[test]
+fn parser_error_into_io_error() {
+    let str = "1,2\n3";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let err = match parser.parse() {
+        Ok(tokens) => panic!("{tokens:?}"),
+        Err(err) => std::io::Error::from(err),
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("expect file to have 2 fields found 1"));
+}
+
+#[test]
+fn parser_crlf() {
+    let str = "1,2,3\r\n4,\"5\",6\r\n7,8,\r\n\"9\r\nx\",\"\",z\r\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[0], vec!["1", "2", "3"]);
+    assert_eq!(tokens[1], vec!["4", "5", "6"]);
+    assert_eq!(tokens[2], vec!["7", "8", ""]);
+    assert_eq!(tokens[3], vec!["9\r\nx", "", "z"]);
+    for (i, token) in tokens.iter().enumerate() {
+        assert_eq!(token.position().record(), i as u64 + 1);
+        assert_eq!(token.position().line(), i as u64 + 1);
+    }
+}
+
+#[test]
+fn parser_bare_cr_and_lf() {
+    let str = "1,2\r3,4\n5,6\r\n7,8";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[3], vec!["7", "8"]);
+    assert_eq!(tokens[3].position().line(), 4);
+    assert_eq!(tokens[3].position().byte(), 13);
+}
+
+#[test]
+fn parser_blank_line_is_skipped() {
+    let str = "a\r\n\r\nb\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1], vec!["b"]);
+    assert_eq!(tokens[1].position().line(), 3);
+    assert_eq!(tokens[1].position().record(), 2);
+
+    // a line with only a quoted empty field is still a record
+    let str = "a\n\"\"\nb\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[1], vec![""]);
+}
+
+#[test]
+fn parser_trailing_blank_lines() {
+    for str in ["a,b\n1,2\n\n", "a,b\r\n1,2\r\n\r\n", "a,b\n\nc,d\n"] {
+        let mut parser = CsvParser::from_string(str.to_string());
+        let tokens = match parser.parse() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("{str:?} {err:?}"),
+        };
+        assert_eq!(tokens.len(), 2, "{str:?}");
+        assert_eq!(tokens[1].len(), 2, "{str:?}");
+    }
+}
+
+#[test]
+fn parser_line_and_record_differ() {
+    let str = "\"a\r\nb\",1\r\n\"c\rd\ne\",2\r\nf,3\r\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[2].position().record(), 3);
+    assert_eq!(tokens[2].position().line(), 6);
+
+    let str = "\"a\r\nb\",1\r\nc\"d,2\r\n";
+    let mut parser = CsvParser::from_string(str.to_string());
+    match parser.parse() {
+        Err(CsvError::UnexpectedQuote { position }) => {
+            assert_eq!(position.record(), 2);
+            assert_eq!(position.line(), 3);
+            assert_eq!(position.column(), 2);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+fn unequal_lines(parser: &CsvParser<std::io::Cursor<String>>) -> Vec<(u64, u64, u64)> {
+    parser
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            CsvError::UnequalLengths {
+                expected,
+                found,
+                position,
+            } => (position.line(), *expected, *found),
+            other => panic!("{other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn parser_flexible() {
+    let str = "1,2,3\n4,5\n6,7,8,9\n";
+    let mut parser = CsvParser::from_string(str.to_string()).flexible(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[1], vec!["4", "5"]);
+    assert_eq!(tokens[2], vec!["6", "7", "8", "9"]);
+    assert_eq!(unequal_lines(&parser), vec![(2, 3, 2), (3, 3, 4)]);
+}
+
+#[test]
+fn parser_ragged_pad_and_truncate() {
+    let str = "1,2,3\n4,5\n6,7,8,9\n";
+
+    let mut parser =
+        CsvParser::from_string(str.to_string()).ragged_rows(RaggedRows::Pad(String::from("")));
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[1], vec!["4", "5", ""]);
+    assert_eq!(tokens[2], vec!["6", "7", "8", "9"]);
+
+    let mut parser = CsvParser::from_string(str.to_string()).ragged_rows(RaggedRows::Truncate);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[1], vec!["4", "5"]);
+    assert_eq!(tokens[2], vec!["6", "7", "8"]);
+
+    let mut parser = CsvParser::from_string(str.to_string())
+        .ragged_rows(RaggedRows::PadAndTruncate(String::from("NULL")));
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[1], vec!["4", "5", "NULL"]);
+    assert_eq!(tokens[2], vec!["6", "7", "8"]);
+    assert_eq!(unequal_lines(&parser), vec![(2, 3, 2), (3, 3, 4)]);
+}
+
+#[test]
+fn parser_flexible_off_by_default() {
+    let str = "1,2,3\n4,5\n";
+    let mut parser = CsvParser::from_string(str.to_string()).flexible(false);
+    assert!(matches!(
+        parser.parse(),
+        Err(CsvError::UnequalLengths { .. })
+    ));
+    assert!(parser.warnings().is_empty());
+}
+
+#[test]
+fn parser_recover() {
+    let str = "a,b,c\n1,2,3\n4,x\"y,6\n7,8\n\"9\"z,10,11\n12,13,14\n";
+    let mut parser = CsvParser::from_string(str.to_string()).recover(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[1], vec!["1", "2", "3"]);
+    assert_eq!(tokens[2], vec!["12", "13", "14"]);
+    assert_eq!(tokens[2].position().record(), 6);
+    assert_eq!(tokens[2].position().line(), 6);
+
+    let bad = parser.quarantine();
+    assert_eq!(bad.len(), 3);
+    assert_eq!(bad[0].raw, "4,x\"y,6");
+    assert!(matches!(bad[0].error, CsvError::UnexpectedQuote { .. }));
+    assert_eq!(bad[0].error.position().line(), 3);
+    assert_eq!(bad[1].raw, "7,8");
+    assert!(matches!(bad[1].error, CsvError::UnequalLengths { .. }));
+    assert_eq!(bad[2].raw, "\"9\"z,10,11");
+    assert!(matches!(bad[2].error, CsvError::UnexpectedQuote { .. }));
+}
+
+#[test]
+fn parser_recover_invalid_utf8() {
+    let bytes = b"a,b\nc,\xffd\ne,f".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).recover(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1], vec!["e", "f"]);
+    assert_eq!(parser.take_quarantine().len(), 1);
+    assert!(parser.quarantine().is_empty());
+}
+
+#[test]
+fn parser_recover_raw_after_skipped_lines() {
+    let str = "a,b\n\n# c\n1\n2,3\n";
+    let mut parser = CsvParser::from_string(str.to_string())
+        .with_header(true)
+        .comment(Some(b'#'))
+        .recover(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0], vec!["2", "3"]);
+    let bad = parser.quarantine();
+    assert_eq!(bad.len(), 1);
+    assert_eq!(bad[0].raw, "1");
+    assert_eq!(bad[0].error.position().line(), 4);
+}
+
+#[test]
+fn parser_recover_max_errors() {
+    let str = "1,2\n3\n4\n5,6\n7\n";
+    let mut parser = CsvParser::from_string(str.to_string())
+        .recover(true)
+        .max_errors(1);
+    let mut good = 0;
+    let err = loop {
+        match parser.next() {
+            Some(Ok(_)) => good += 1,
+            Some(Err(err)) => break err,
+            None => panic!("expected too many errors"),
+        }
+    };
+    assert_eq!(good, 1);
+    match err {
+        CsvError::TooManyErrors { max, position } => {
+            assert_eq!(max, 1);
+            assert_eq!(position.line(), 3);
+        }
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(parser.quarantine().len(), 2);
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn parser_byte_records() {
+    let bytes = b"caf\xe9,\"\xff\"\"\x00\",3\r\nna\xefve,b,\"c\"\r\n".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    let tokens = match parser.byte_records().collect::<Result<Vec<ByteRecord>, CsvError>>() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec![&b"caf\xe9"[..], b"\xff\"\x00", b"3"]);
+    assert_eq!(tokens[1], vec![&b"na\xefve"[..], b"b", b"c"]);
+    assert_eq!(tokens[1].position().line(), 2);
+
+    assert!(matches!(
+        tokens[0].get_str(0),
+        Some(Err(CsvError::InvalidUtf8 { field: 1, .. }))
+    ));
+    assert!(matches!(tokens[0].get_str(2), Some(Ok("3"))));
+    assert!(tokens[0].get_str(3).is_none());
+}
+
+#[test]
+fn parser_utf8_lossy() {
+    let bytes = b"caf\xe9,ok\n".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).utf8(Utf8Mode::Lossy);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[0], vec!["caf\u{FFFD}", "ok"]);
+}
+
+#[test]
+fn parser_byte_record_to_record() {
+    let record = ByteRecord::from(vec![&b"a"[..], b"\xc3\xa9", b"\xc3"]);
+    match Record::from_byte_record(record.clone()) {
+        Err(CsvError::InvalidUtf8 { field, .. }) => assert_eq!(field, 3),
+        other => panic!("{other:?}"),
+    }
+    let lossy = Record::from_byte_record_lossy(record);
+    assert_eq!(lossy, vec!["a", "é", "\u{FFFD}"]);
+    let back = ByteRecord::from(lossy);
+    assert_eq!(back.get(1), Some(&b"\xc3\xa9"[..]));
+}
+
+fn utf16(str: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let units = bom.then_some(0xFEFF).into_iter().chain(str.encode_utf16());
+    for unit in units {
+        match big_endian {
+            true => bytes.extend_from_slice(&unit.to_be_bytes()),
+            false => bytes.extend_from_slice(&unit.to_le_bytes()),
+        }
+    }
+    bytes
+}
+
+#[test]
+fn parser_utf8_bom() {
+    let bytes = b"\xef\xbb\xbfname,price\napple,1\n".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).with_header(true);
+    match parser.headers() {
+        Ok(Some(headers)) => assert_eq!(headers, ["name", "price"]),
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(parser.input_encoding(), Some(Encoding::Utf8));
+}
+
+#[test]
+fn parser_utf16_bom() {
+    let str = "naïve,𝄞\r\n\"a,b\",c\r\n";
+    for big_endian in [false, true] {
+        let bytes = utf16(str, big_endian, true);
+        let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+        let tokens = match parser.parse() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("{err:?}"),
+        };
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0], vec!["naïve", "𝄞"]);
+        assert_eq!(tokens[1], vec!["a,b", "c"]);
+    }
+}
+
+#[test]
+fn parser_explicit_encoding() {
+    let bytes = utf16("a,b\nc,d\n", false, false);
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).encoding(Encoding::Utf16Le);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[1], vec!["c", "d"]);
+
+    let bytes = b"caf\xe9,\x80 5,\x93q\x94\n".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).encoding(Encoding::Windows1252);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[0], vec!["café", "€ 5", "“q”"]);
+
+    let bytes = b"caf\xe9,\x80\n".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).encoding(Encoding::Latin1);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[0], vec!["café", "\u{80}"]);
+}
+
+#[test]
+fn parser_encoding_mismatch() {
+    let bytes = utf16("a,b\n", false, true);
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).encoding(Encoding::Utf8);
+    match parser.parse() {
+        Err(CsvError::EncodingMismatch {
+            expected, found, ..
+        }) => {
+            assert_eq!(expected, Encoding::Utf8);
+            assert_eq!(found, Encoding::Utf16Le);
+        }
+        other => panic!("{other:?}"),
+    }
+
+    let bytes = utf16("a,b\nc,d\n", true, false);
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    match parser.parse() {
+        Err(CsvError::EncodingMismatch { found, .. }) => assert_eq!(found, Encoding::Utf16Be),
+        other => panic!("{other:?}"),
+    }
+
+    // one NUL is too little to guess UTF-16 from
+    let mut parser = CsvParser::from_string(String::from("\0\r\n"));
+    match parser.parse() {
+        Ok(tokens) => assert_eq!(tokens, vec![vec!["\0"]]),
+        Err(err) => panic!("{err:?}"),
+    }
+
+    let mut bytes = utf16("a,b\n", false, true);
+    bytes.push(b'c');
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    match parser.parse() {
+        Err(err @ CsvError::InvalidEncoding { .. }) => {
+            assert_eq!(err.position().byte(), 10);
+            assert_eq!(err.to_string(), "at byte 10: invalid UTF-16LE");
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_utf16_across_reads() {
+    let row = "𝄞é,x\n";
+    let str = row.repeat(3000);
+    let bytes = utf16(&str, false, true);
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes));
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3000);
+    assert!(tokens.iter().all(|token| *token == vec!["𝄞é", "x"]));
+}
+
+#[test]
+fn parser_read_record_reuses_buffers() {
+    let str = "name,city\nlonger name,longer city\nann,oslo\nbo,rome\n";
+    let mut parser = CsvParser::from_string(str.to_string()).with_header(true);
+    let mut record = Record::new();
+    let mut seen = Vec::new();
+    let mut ptr = None;
+    loop {
+        match parser.read_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => panic!("{err:?}"),
+        }
+        // the first record is the longest so later ones fit in its buffer
+        let start = *ptr.get_or_insert(record.as_slice().as_ptr());
+        assert_eq!(record.as_slice().as_ptr(), start);
+        assert_eq!(record.get("city"), record.get(1));
+        seen.push(Vec::<String>::from(record.clone()));
+    }
+    assert_eq!(
+        seen,
+        vec![
+            vec!["longer name", "longer city"],
+            vec!["ann", "oslo"],
+            vec!["bo", "rome"]
+        ]
+    );
+    assert!(record.is_empty());
+    assert!(matches!(parser.read_record(&mut record), Ok(false)));
+}
+
+#[test]
+fn parser_read_byte_record_recover() {
+    let bytes = b"a,b\nc,\xffd\n1\ne,\"f\"\"\"".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).recover(true);
+    let mut record = ByteRecord::new();
+    let mut seen = Vec::new();
+    while match parser.read_byte_record(&mut record) {
+        Ok(more) => more,
+        Err(err) => panic!("{err:?}"),
+    } {
+        seen.push(record.clone());
+    }
+    assert_eq!(seen.len(), 3);
+    assert_eq!(seen[1], vec![b"c".as_slice(), b"\xffd"]);
+    assert_eq!(seen[2], vec!["e", "f\""]);
+    assert_eq!(seen[2].position().record(), 4);
+    assert_eq!(parser.quarantine().len(), 1);
+
+    let bytes = b"a,b\nc,\xffd\ne,f".to_vec();
+    let mut parser = CsvParser::new(std::io::Cursor::new(bytes)).recover(true);
+    let mut record = Record::new();
+    let mut count = 0;
+    while let Ok(true) = parser.read_record(&mut record) {
+        count += 1;
+    }
+    assert_eq!(count, 2);
+    assert_eq!(record.len(), 0);
+    assert!(matches!(
+        parser.quarantine()[0].error,
+        CsvError::InvalidUtf8 { field: 2, .. }
+    ));
+}
+
+// writes `data` to a file in the temp dir, `name` is its file name
+fn temp_file(name: &str, data: impl AsRef<[u8]>) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("rust_csv_parser_{}_{name}", std::process::id()));
+    if let Err(err) = std::fs::write(&path, data) {
+        panic!("{err:?}");
+    }
+    path
+}
+
+// each record or error as its debug string
+fn debug(records: impl IntoIterator<Item = crate::Result<Record>>) -> Vec<String> {
+    records
+        .into_iter()
+        .map(|record| format!("{record:?}"))
+        .collect()
+}
+
+fn first_record<R: std::io::Read>(parser: std::io::Result<CsvParser<R>>) -> Vec<String> {
+    let mut parser = match parser {
+        Ok(parser) => parser,
+        Err(err) => panic!("{err:?}"),
+    };
+    match parser.next() {
+        Some(Ok(record)) => record.iter().map(String::from).collect(),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn parser_from_path_extensions() {
+    let tsv = temp_file("data.tsv", "a\tb,c\n");
+    let txt = temp_file("data.txt", "a\tb,c\n");
+    let bare = temp_file("data", "a\tb,c\n");
+    assert_eq!(first_record(CsvParser::from_path(&tsv)), ["a", "b,c"]);
+    let gz = std::path::Path::new("data.TSV.gz");
+    assert_eq!(crate::path_dialect(gz).delimiter, b'\t');
+    assert_eq!(first_record(CsvParser::from_path(&txt)), ["a\tb", "c"]);
+    assert_eq!(first_record(CsvParser::from_path(&bare)), ["a\tb", "c"]);
+    assert_eq!(
+        first_record(CsvParser::from_path_strict(&tsv)),
+        ["a", "b,c"]
+    );
+    for path in [&txt, &bare] {
+        match CsvParser::from_path_strict(path) {
+            Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput),
+            Ok(_) => panic!("{path:?} should be rejected"),
+        }
+    }
+    let dialect = Dialect::new().delimiter(b';');
+    let dat = temp_file("data.dat", "a;b\tc\n");
+    assert_eq!(
+        first_record(CsvParser::from_path_with_dialect(&tsv, dialect)),
+        ["a\tb,c"]
+    );
+    assert_eq!(
+        first_record(CsvParser::from_path_with_dialect(&dat, dialect)),
+        ["a", "b\tc"]
+    );
+    for path in [tsv, txt, bare, dat] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn parser_comments_and_preamble() {
+    let str = "Instrument export\r\n# unit: mV\r\n\r\nid,v\r\n1,\"#2\"\r\n# note\r\n\r\n#3,4\r\n";
+    let mut parser = CsvParser::from_string(String::from(str))
+        .with_header(true)
+        .skip_rows(1)
+        .comment(Some(b'#'));
+    match parser.headers() {
+        Ok(Some(headers)) => assert_eq!(headers, ["id", "v"]),
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(parser.preamble(), ["Instrument export", "# unit: mV", ""]);
+    let records = match parser.parse() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].get("v"), Some("#2"));
+    assert_eq!(records[0].position().line(), 5);
+    assert_eq!(records[0].position().record(), 2);
+
+    // without skipping a blank line is a record with one empty field
+    let mut parser = CsvParser::from_string(String::from("a,b\n\n1,2\n")).skip_blank_lines(false);
+    assert!(matches!(
+        parser.nth(1),
+        Some(Err(CsvError::UnequalLengths { .. }))
+    ));
+    assert!(parser.preamble().is_empty());
+}
+
+#[cfg(feature = "async")]
+mod asynchronous;
+
+mod borrowed;
+mod compression;
+mod parallel;
+mod push;
+mod tokenizer;
+mod writer;
+
+#[cfg(feature = "serde")]
+mod deserializer;
+
+#[cfg(feature = "serde")]
+mod serializer;
+
+mod sniffer;
+
+#[cfg(feature = "mmap")]
+mod mmap;
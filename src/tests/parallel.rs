@@ -0,0 +1,135 @@
+use proptest::prelude::*;
+
+use super::{debug, temp_file};
+use crate::{CsvParser, Dialect, Escape, ParallelParser, Record, Terminator};
+
+#[test]
+fn parallel_same_records_as_parser() {
+    let mut str = String::from("\u{feff}id,text,n\r\n");
+    for i in 0..500 {
+        match i % 4 {
+            0 => str.push_str(&format!("{i},plain,1\r\n")),
+            1 => str.push_str(&format!("{i},\"two\r\nlines, \"\"quoted\"\"\",2\n")),
+            2 => str.push_str(&format!("{i},\"\n\n\",3\r")),
+            _ => str.push_str(&format!("{i},\"é\"\"\",4\r\n")),
+        }
+    }
+    let path = temp_file("same.csv", str.as_bytes());
+    let sequential = match CsvParser::from_path(&path) {
+        Ok(parser) => parser.with_header(true),
+        Err(err) => panic!("{err:?}"),
+    };
+    let parallel = match ParallelParser::from_path(&path) {
+        Ok(parser) => parser.with_header(true).threads(3).chunk_size(64),
+        Err(err) => panic!("{err:?}"),
+    };
+    let expected = debug(sequential);
+    let found: Vec<Record> = match parallel.collect() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(expected.len(), 500);
+    assert_eq!(expected, debug(found.iter().cloned().map(Ok)));
+    assert_eq!(found[499].get("id"), Some("499"));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn parallel_unordered() {
+    let str = (0..300).fold(String::new(), |mut str, i| {
+        str.push_str(&format!("{i},\"a\nb\"\n"));
+        str
+    });
+    let path = temp_file("unordered.csv", str.as_bytes());
+    let parallel = match ParallelParser::from_path(&path) {
+        Ok(parser) => parser.threads(4).chunk_size(100).ordered(false),
+        Err(err) => panic!("{err:?}"),
+    };
+    let mut found: Vec<Record> = match parallel.collect() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    found.sort_by_key(|record| record.position().record());
+    let sequential = match CsvParser::from_path(&path) {
+        Ok(parser) => parser,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(debug(sequential), debug(found.into_iter().map(Ok)));
+    let _ = std::fs::remove_file(path);
+}
+
+proptest! {
+    #[test]
+    fn parallel_same_result_for_any_chunk_size(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", ",", "\"", "\\", "\r", "\n", "\r\n", "~", "#"]),
+            0..60,
+        ),
+        size in 1u64..16,
+        escape in prop::option::of(Just(b'\\')),
+        escaping in prop::sample::select(vec![Escape::Doubled, Escape::Backslash, Escape::None]),
+        quoting in any::<bool>(),
+        any in any::<bool>(),
+        header in any::<bool>(),
+        comment in prop::option::of(Just(b'#')),
+        skip_rows in 0u64..3,
+        skip_blank_lines in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let terminator = match any {
+            true => Terminator::Any(b'~'),
+            false => Terminator::CRLF,
+        };
+        let dialect = Dialect::new()
+            .escape(escape)
+            .escaping(escaping)
+            .quoting(quoting).terminator(terminator);
+        let path = temp_file("prop.csv", str.as_bytes());
+        let sequential = CsvParser::from_path(&path)?
+            .dialect(dialect)
+            .with_header(header)
+            .comment(comment)
+            .skip_rows(skip_rows)
+            .skip_blank_lines(skip_blank_lines)
+            .flexible(true);
+        let parallel = ParallelParser::from_path(&path)?
+            .dialect(dialect)
+            .with_header(header)
+            .comment(comment)
+            .skip_rows(skip_rows)
+            .skip_blank_lines(skip_blank_lines)
+            .flexible(true)
+            .threads(2)
+            .chunk_size(size);
+        let (expected, found) = (debug(sequential), debug(parallel));
+        let _ = std::fs::remove_file(&path);
+        prop_assert_eq!(expected, found);
+    }
+}
+
+#[test]
+fn parallel_comments_and_preamble() {
+    let mut str = String::from("title\n# note\nid,n\n");
+    for i in 0..200 {
+        str.push_str(&format!("{i},1\n\n# {i}\n"));
+    }
+    let path = temp_file("preamble.csv", str.as_bytes());
+    let mut parallel = match ParallelParser::from_path(&path) {
+        Ok(parser) => parser
+            .with_header(true)
+            .skip_rows(1)
+            .comment(Some(b'#'))
+            .threads(3)
+            .chunk_size(50),
+        Err(err) => panic!("{err:?}"),
+    };
+    let found: Vec<Record> = match parallel.by_ref().collect() {
+        Ok(records) => records,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(parallel.preamble(), ["title", "# note"]);
+    assert_eq!(found.len(), 200);
+    assert_eq!(found[199].get("id"), Some("199"));
+    assert_eq!(found[199].position().line(), 601);
+    let _ = std::fs::remove_file(path);
+}
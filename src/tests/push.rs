@@ -0,0 +1,155 @@
+use proptest::prelude::*;
+
+use super::debug;
+use crate::{
+    ByteRecord, CsvCore, CsvError, CsvParser, Dialect, Escape, Options, PushParser, Record, Result,
+    SliceParser,
+};
+
+// feeds `chunks` one at a time and collects what comes out like an iterator
+fn push_all<'a>(
+    parser: &mut PushParser,
+    chunks: impl IntoIterator<Item = &'a [u8]>,
+) -> Vec<Result<Record>> {
+    let mut records = Vec::new();
+    for chunk in chunks.into_iter().map(Some).chain([None]) {
+        let found = match chunk {
+            Some(chunk) => parser.feed(chunk),
+            None => parser.finish(),
+        };
+        match found {
+            Ok(found) => records.extend(found.into_iter().map(Ok)),
+            Err(err) => {
+                records.push(Err(err));
+                break;
+            }
+        }
+    }
+    records
+}
+
+#[test]
+fn push_every_split_point() {
+    let str = "\u{feff}id,text\r\n1,\"two\r\nlines, \"\"quoted\"\"\"\r\n2,é\r3,";
+    let expected = debug(CsvParser::from_string(String::from(str)).with_header(true));
+    assert_eq!(expected.len(), 3);
+    let bytes = str.as_bytes();
+    for i in 0..=bytes.len() {
+        let (first, second) = bytes.split_at(i);
+        let found = push_all(&mut PushParser::new().with_header(true), [first, second]);
+        assert_eq!(expected, debug(found), "{i}");
+    }
+    let found = push_all(&mut PushParser::new().with_header(true), bytes.chunks(1));
+    assert_eq!(expected, debug(found));
+}
+
+#[test]
+fn push_error_after_records() {
+    let mut parser = PushParser::new();
+    match parser.feed(b"a,b\n1,2\n3\n4,5\n") {
+        Ok(records) => assert_eq!(records.len(), 2),
+        Err(err) => panic!("{err:?}"),
+    }
+    assert!(!parser.is_done());
+    assert!(matches!(
+        parser.feed(b"6,7\n"),
+        Err(CsvError::UnequalLengths { found: 1, .. })
+    ));
+    assert!(parser.is_done());
+    assert!(matches!(parser.finish(), Ok(records) if records.is_empty()));
+}
+
+#[test]
+fn core_keeps_state_between_chunks() {
+    let mut core = CsvCore::new(Dialect::new()).trim_space(true);
+    let mut record = ByteRecord::new();
+    let mut complete = Vec::new();
+    for chunk in [&b" a ,\"b"[..], b",\"\"c\"", b"\r", b"\nd"] {
+        let mut input = chunk;
+        while !input.is_empty() {
+            let (used, result) = core.feed(input, &mut record);
+            input = &input[used..];
+            if matches!(result, Ok(true)) {
+                complete.push(std::mem::take(&mut record));
+            }
+        }
+    }
+    assert_eq!(complete.len(), 1);
+    assert!(matches!(core.finish(&mut record), Ok(true)));
+    assert_eq!(
+        complete[0].iter().collect::<Vec<_>>(),
+        [&b"a"[..], b"b,\"c"]
+    );
+    assert_eq!(record.iter().collect::<Vec<_>>(), [&b"d"[..]]);
+    assert_eq!(record.position().line(), 2);
+}
+
+proptest! {
+    #[test]
+    fn push_same_records_as_parser(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", ",", "\"", "\\", "\r", "\n", "\u{feff}", "#"]),
+            0..40,
+        ),
+        sizes in prop::collection::vec(1usize..6, 1..8),
+        escape in prop::option::of(Just(b'\\')),
+        escaping in prop::sample::select(vec![Escape::Doubled, Escape::Backslash, Escape::None]),
+        quoting in any::<bool>(),
+        header in any::<bool>(),
+        comment in prop::option::of(Just(b'#')),
+        blank in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let dialect = Dialect::new()
+            .escape(escape)
+            .escaping(escaping)
+            .quoting(quoting);
+        let mut sequential = CsvParser::from_string(str.clone())
+            .dialect(dialect)
+            .with_header(header)
+            .comment(comment)
+            .skip_blank_lines(blank)
+            .flexible(true);
+        let expected = debug(sequential.by_ref());
+        let mut chunks = Vec::new();
+        let mut rest = str.as_bytes();
+        for size in sizes.iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            chunks.push(chunk);
+            rest = tail;
+        }
+        let mut parser = PushParser::new()
+            .dialect(dialect)
+            .with_header(header)
+            .comment(comment)
+            .skip_blank_lines(blank)
+            .flexible(true);
+        let found = debug(push_all(&mut parser, chunks));
+        prop_assert_eq!(expected, found);
+        prop_assert_eq!(sequential.preamble(), parser.preamble());
+    }
+}
+
+#[test]
+fn push_same_options_as_other_parsers() {
+    let str = "title\nid;text\n# note\n1; a \n\n2;\"b;c\"\n";
+    let options = Options::new()
+        .with_header(true)
+        .delimiter(b';')
+        .trim_space(true)
+        .comment(Some(b'#'))
+        .skip_rows(1);
+    let expected = debug(CsvParser::from_string(String::from(str)).options(options.clone()));
+    assert_eq!(expected.len(), 2);
+    let slices = SliceParser::new(str).options(options.clone());
+    assert_eq!(
+        expected,
+        debug(slices.map(|record| record.map(Record::from)))
+    );
+    let mut parser = PushParser::new().options(options);
+    assert_eq!(expected, debug(push_all(&mut parser, [str.as_bytes()])));
+    assert_eq!(parser.preamble(), ["title"]);
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CsvError, CsvParser, CsvWriter};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Tier {
+    Free,
+    Paid,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Address {
+    city: String,
+    zip: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Customer {
+    name: String,
+    balance: f64,
+    nick: Option<String>,
+    tier: Tier,
+    address: Address,
+}
+
+fn customers() -> Vec<Customer> {
+    vec![
+        Customer {
+            name: String::from("ann, \"the\" first"),
+            balance: 0.1,
+            nick: None,
+            tier: Tier::Paid,
+            address: Address {
+                city: String::from("Oslo\nNorth"),
+                zip: Some(150),
+            },
+        },
+        Customer {
+            name: String::from("bob"),
+            balance: -1e300,
+            nick: Some(String::from("b")),
+            tier: Tier::Free,
+            address: Address {
+                city: String::new(),
+                zip: None,
+            },
+        },
+    ]
+}
+
+fn write(mut writer: CsvWriter<Vec<u8>>, customers: &[Customer]) -> String {
+    for customer in customers {
+        if let Err(err) = writer.serialize(customer) {
+            panic!("{err:?}");
+        }
+    }
+    match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+        Err(err) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn serializer_header_and_nesting() {
+    let str = write(CsvWriter::new(Vec::new()), &customers()[1..]);
+    assert_eq!(
+        str,
+        "name,balance,nick,tier,address.city,address.zip\r\nbob,-1e300,b,Free,,\r\n"
+    );
+
+    let writer = CsvWriter::new(Vec::new())
+        .header_separator("_")
+        .with_header(false);
+    let str = write(writer, &customers()[1..]);
+    assert_eq!(str, "bob,-1e300,b,Free,,\r\n");
+
+    let writer = CsvWriter::new(Vec::new()).header_separator("_");
+    let str = write(writer, &customers()[1..]);
+    assert!(str.starts_with("name,balance,nick,tier,address_city,address_zip\r\n"));
+}
+
+#[test]
+fn serializer_round_trip() {
+    let customers = customers();
+    let str = write(CsvWriter::new(Vec::new()), &customers);
+    let mut parser = CsvParser::from_string(str)
+        .with_header(true)
+        .header_separator(".");
+    let parsed = match parser
+        .deserialize::<Customer>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(parsed, customers);
+}
+
+#[test]
+fn serializer_none_nested_struct() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        address: Option<Address>,
+        age: u32,
+    }
+    let people = vec![
+        Person {
+            name: String::from("a"),
+            address: Some(Address {
+                city: String::from("Oslo"),
+                zip: Some(150),
+            }),
+            age: 2,
+        },
+        Person {
+            name: String::from("b"),
+            address: None,
+            age: 3,
+        },
+    ];
+    let mut writer = CsvWriter::new(Vec::new());
+    for person in &people {
+        if let Err(err) = writer.serialize(person) {
+            panic!("{err:?}");
+        }
+    }
+    let str = match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(
+        str,
+        "name,address.city,address.zip,age\r\na,Oslo,150,2\r\nb,,,3\r\n"
+    );
+    let mut parser = CsvParser::from_string(str)
+        .with_header(true)
+        .header_separator(".");
+    let parsed = match parser
+        .deserialize::<Person>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(parsed, people);
+}
+
+#[test]
+fn serializer_none_nested_struct_first() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        address: Option<Address>,
+        age: u32,
+    }
+    let mut writer = CsvWriter::new(Vec::new());
+    let first = Person {
+        name: String::from("a"),
+        address: None,
+        age: 1,
+    };
+    if let Err(err) = writer.serialize(&first) {
+        panic!("{err:?}");
+    }
+    let second = Person {
+        name: String::from("b"),
+        address: Some(Address {
+            city: String::from("x"),
+            zip: Some(2),
+        }),
+        age: 1,
+    };
+    match writer.serialize(&second) {
+        Err(CsvError::Serialize { field, .. }) => {
+            assert_eq!(field.as_deref(), Some("address.city"))
+        }
+        other => panic!("{other:?}"),
+    }
+    // what was written still reads back
+    let str = match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(str, "name,address,age\r\na,,1\r\n");
+    let parser = CsvParser::from_string(str).with_header(true);
+    match parser.collect::<Result<Vec<_>, _>>() {
+        Ok(records) => assert_eq!(records.len(), 1),
+        Err(err) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn serializer_tuples_and_errors() {
+    let mut writer = CsvWriter::new(Vec::new());
+    if let Err(err) = writer.serialize(&("a", 1, None::<u8>, true)) {
+        panic!("{err:?}");
+    }
+    match writer.into_inner() {
+        Ok(bytes) => assert_eq!(bytes, b"a,1,,true\r\n"),
+        Err(err) => panic!("{err:?}"),
+    }
+
+    #[derive(Serialize)]
+    enum Shape {
+        Circle(f64),
+    }
+    #[derive(Serialize)]
+    struct Row {
+        id: u32,
+        shape: Shape,
+    }
+    let mut writer = CsvWriter::new(Vec::new());
+    match writer.serialize(&Row {
+        id: 1,
+        shape: Shape::Circle(2.0),
+    }) {
+        Err(CsvError::Serialize { field, .. }) => assert_eq!(field.as_deref(), Some("shape")),
+        other => panic!("{other:?}"),
+    }
+}
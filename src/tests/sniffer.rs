@@ -0,0 +1,55 @@
+use std::io::Cursor;
+
+use crate::{CsvParser, Dialect, Sniffer, Terminator};
+
+#[test]
+fn sniffer_semicolon_with_header() {
+    let str = "name;age;city\r\nann;30;\"Oslo; North\"\r\nbob;7;Rome\r\ncy;41;Lima\r\n";
+    let sniffed = match Sniffer::new().sniff(Cursor::new(str)) {
+        Ok(sniffed) => sniffed,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(sniffed.dialect, Dialect::new().delimiter(b';'));
+    assert!(sniffed.has_header);
+    assert_eq!(sniffed.field_count, 3);
+
+    let mut parser = CsvParser::from_string(String::from(str))
+        .dialect(sniffed.dialect)
+        .with_header(sniffed.has_header);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0].get("city"), Some("Oslo; North"));
+}
+
+#[test]
+fn sniffer_tab_without_header() {
+    let str = "1\t2.5\tx,y\n3\t4\tz\n5\t6\tw\n";
+    let sniffed = match Sniffer::new().sniff(Cursor::new(str)) {
+        Ok(sniffed) => sniffed,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(sniffed.dialect.delimiter, b'\t');
+    assert_eq!(sniffed.dialect.terminator, Terminator::Any(b'\n'));
+    assert!(!sniffed.has_header);
+    assert_eq!(sniffed.field_count, 3);
+}
+
+#[test]
+fn sniffer_single_quote_and_sample_size() {
+    let mut str = String::from("'id'|'note'\r\n");
+    for i in 0..100 {
+        str.push_str(&format!("{i}|'a|b'\r\n"));
+    }
+    let sniffed = match Sniffer::new().sample_size(100).sniff(Cursor::new(str)) {
+        Ok(sniffed) => sniffed,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(sniffed.dialect.delimiter, b'|');
+    assert_eq!(sniffed.dialect.quote, b'\'');
+    assert_eq!(sniffed.dialect.terminator, Terminator::CRLF);
+    assert!(sniffed.has_header);
+    assert_eq!(sniffed.field_count, 2);
+}
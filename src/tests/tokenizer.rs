@@ -0,0 +1,158 @@
+use std::io::{self, Read};
+
+use proptest::prelude::*;
+
+use crate::{ByteRecord, CsvCore, CsvParser, Dialect, Escape, Terminator};
+
+// hands out at most `size` bytes per read so every field and terminator
+// ends up split across buffers somewhere
+struct Chunks {
+    data: Vec<u8>,
+    pos: usize,
+    size: usize,
+}
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.size.min(buf.len()).min(self.data.len() - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+fn chunked(str: &str, size: usize) -> CsvParser<Chunks> {
+    CsvParser::new(Chunks {
+        data: str.as_bytes().to_vec(),
+        pos: 0,
+        size,
+    })
+}
+
+#[test]
+fn tokenizer_fields_across_reads() {
+    let str = "abc,\"d,\"\"e\"\"\r\nf\",g\r\n\r\n,h\r";
+    let mut parser = chunked(str, 1).flexible(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], vec!["abc", "d,\"e\"\r\nf", "g"]);
+    assert_eq!(tokens[1], vec!["", "h"]);
+    assert_eq!(tokens[1].position().line(), 4);
+    assert_eq!(tokens[1].position().byte(), 22);
+}
+
+#[test]
+fn tokenizer_trim_last_field() {
+    let str = String::from(" a , b ");
+    let mut parser = CsvParser::from_string(str).trim_space(true);
+    let tokens = match parser.parse() {
+        Ok(tokens) => tokens,
+        Err(err) => panic!("{err:?}"),
+    };
+    assert_eq!(tokens[0], vec!["a", "b"]);
+}
+
+fn results<R: Read>(parser: &mut CsvParser<R>) -> Vec<String> {
+    let mut ret: Vec<String> = parser.by_ref().map(|token| format!("{token:?}")).collect();
+    ret.extend(parser.quarantine().iter().map(|bad| format!("{bad:?}")));
+    ret
+}
+
+proptest! {
+    #[test]
+    fn tokenizer_same_result_for_any_read_size(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", " ", ",", "\"", "\\", "\r", "\n", "~"]),
+            0..40,
+        ),
+        size in 1usize..8,
+        escape in prop::option::of(Just(b'\\')),
+        any in any::<bool>(),
+        recover in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let terminator = match any {
+            true => Terminator::Any(b'~'),
+            false => Terminator::CRLF,
+        };
+        let dialect = Dialect::new().escape(escape).terminator(terminator);
+        let mut whole = CsvParser::from_string(str.clone())
+            .dialect(dialect)
+            .flexible(true)
+            .recover(recover);
+        let mut parts = chunked(&str, size)
+            .dialect(dialect)
+            .flexible(true)
+            .recover(recover);
+        prop_assert_eq!(results(&mut whole), results(&mut parts));
+    }
+
+    #[test]
+    fn tokenizer_skim_finds_the_same_record_ends(
+        chars in prop::collection::vec(
+            prop::sample::select(vec!["a", "é", ",", "\"", "\\", "\r", "\n", "~", "#"]),
+            0..40,
+        ),
+        size in 1usize..8,
+        escape in prop::option::of(Just(b'\\')),
+        escaping in prop::sample::select(vec![Escape::Doubled, Escape::Backslash, Escape::None]),
+        quoting in any::<bool>(),
+        any in any::<bool>(),
+        comment in prop::option::of(Just(b'#')),
+        skip_rows in 0u64..3,
+        skip_blank_lines in any::<bool>(),
+    ) {
+        let str: String = chars.concat();
+        let terminator = match any {
+            true => Terminator::Any(b'~'),
+            false => Terminator::CRLF,
+        };
+        let dialect = Dialect::new()
+            .escape(escape)
+            .escaping(escaping)
+            .quoting(quoting)
+            .terminator(terminator);
+        let core = CsvCore::new(dialect)
+            .comment(comment)
+            .skip_rows(skip_rows)
+            .skip_blank_lines(skip_blank_lines);
+        let end = |core: &CsvCore| (core.position(), core.record);
+
+        // up to the first error, which skimming does not look for
+        let (mut read, mut expected, mut failed) = (core.clone(), Vec::new(), false);
+        let mut record = ByteRecord::new();
+        'read: for mut part in str.as_bytes().chunks(size) {
+            while !part.is_empty() {
+                let (used, result) = read.feed(part, &mut record);
+                part = &part[used..];
+                match result {
+                    Ok(true) => expected.push(end(&read)),
+                    Ok(false) => {}
+                    Err(_) => {
+                        failed = true;
+                        break 'read;
+                    }
+                }
+            }
+        }
+        let (mut skim, mut found) = (core, Vec::new());
+        for mut part in str.as_bytes().chunks(size) {
+            while !part.is_empty() {
+                let (used, ended) = skim.skim(part, 0);
+                part = &part[used..];
+                if ended {
+                    found.push(end(&skim));
+                }
+            }
+        }
+        if failed {
+            found.truncate(expected.len());
+        } else {
+            prop_assert_eq!(end(&read), end(&skim));
+        }
+        prop_assert_eq!(expected, found);
+    }
+}
@@ -0,0 +1,172 @@
+use proptest::prelude::*;
+
+use crate::{CsvError, CsvParser, CsvWriter, Dialect, Escape, QuoteStyle, Terminator};
+
+fn write(writer: CsvWriter<Vec<u8>>, rows: &[Vec<&str>]) -> String {
+    let mut writer = writer;
+    for row in rows {
+        if let Err(err) = writer.write_record(row) {
+            panic!("{err:?}");
+        }
+    }
+    match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+        Err(err) => panic!("{err:?}"),
+    }
+}
+
+fn parse(parser: CsvParser<std::io::Cursor<String>>) -> Vec<Vec<String>> {
+    let mut parser = parser;
+    match parser.parse() {
+        Ok(tokens) => tokens.into_iter().map(Vec::<String>::from).collect(),
+        Err(err) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn writer_quote_necessary() {
+    let rows = vec![
+        vec!["1", "a,b", "say \"hi\""],
+        vec!["", "line\nbreak", "cr\r"],
+    ];
+    let str = write(CsvWriter::new(Vec::new()), &rows);
+    assert_eq!(
+        str,
+        "1,\"a,b\",\"say \"\"hi\"\"\"\r\n,\"line\nbreak\",\"cr\r\"\r\n"
+    );
+}
+
+#[test]
+fn writer_quote_styles() {
+    let rows = vec![vec!["1", "-2.5e3", "x", ""]];
+    let str = write(
+        CsvWriter::new(Vec::new()).quote_style(QuoteStyle::Always),
+        &rows,
+    );
+    assert_eq!(str, "\"1\",\"-2.5e3\",\"x\",\"\"\r\n");
+
+    let str = write(
+        CsvWriter::new(Vec::new()).quote_style(QuoteStyle::NonNumeric),
+        &rows,
+    );
+    assert_eq!(str, "1,-2.5e3,\"x\",\"\"\r\n");
+
+    let rows = vec![vec!["a,b", "c\"d"]];
+    let str = write(
+        CsvWriter::new(Vec::new()).quote_style(QuoteStyle::Never),
+        &rows,
+    );
+    assert_eq!(str, "a,b,c\"d\r\n");
+}
+
+#[test]
+fn writer_dialect() {
+    let rows = vec![vec!["a;b", "it's", "back\\slash"], vec!["c", "d~e", "f"]];
+    let dialect = Dialect::new()
+        .delimiter(b';')
+        .quote(b'\'')
+        .escape(Some(b'\\'))
+        .terminator(Terminator::Any(b'~'));
+    let str = write(CsvWriter::new(Vec::new()).dialect(dialect), &rows);
+    assert_eq!(str, "'a;b';'it\\'s';back\\slash~c;'d~e';f~");
+
+    let parsed = parse(CsvParser::from_string(str).dialect(dialect));
+    assert_eq!(parsed, rows);
+}
+
+#[test]
+fn writer_unwritable_quote() {
+    let mut writer = CsvWriter::new(Vec::new()).escaping(Escape::None);
+    if let Err(err) = writer.write_record(["a", "b,c"]) {
+        panic!("{err:?}");
+    }
+    match writer.write_record(["d", "say \"hi\""]) {
+        Err(CsvError::UnwritableQuote { field, position }) => {
+            assert_eq!(field, 1);
+            assert_eq!(position.record(), 2);
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+fn field() -> impl Strategy<Value = String> {
+    let special = prop_oneof![
+        Just(','),
+        Just(';'),
+        Just('"'),
+        Just('\''),
+        Just('\\'),
+        Just('^'),
+        Just('\r'),
+        Just('\n'),
+        Just(' '),
+        Just('\u{FEFF}'),
+        any::<char>(),
+    ];
+    prop::collection::vec(special, 0..8).prop_map(|chars| chars.into_iter().collect())
+}
+
+fn rows() -> impl Strategy<Value = Vec<Vec<String>>> {
+    (1usize..5)
+        .prop_flat_map(|len| prop::collection::vec(prop::collection::vec(field(), len), 0..8))
+}
+
+fn dialect() -> impl Strategy<Value = Dialect> {
+    (
+        prop::sample::select(vec![b',', b';', b'\t', b'|']),
+        prop::sample::select(vec![b'"', b'\'']),
+        prop::option::of(prop::sample::select(vec![b'\\', b'^'])),
+        prop::sample::select(vec![Terminator::CRLF, Terminator::Any(b'~')]),
+        prop::sample::select(vec![Escape::Doubled, Escape::Backslash, Escape::None]),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(delimiter, quote, escape, terminator, escaping, quoting)| {
+                Dialect::new()
+                    .delimiter(delimiter)
+                    .quote(quote)
+                    .escape(escape)
+                    .terminator(terminator)
+                    .escaping(escaping)
+                    // without quotes only backslash escapes can write every field
+                    .quoting(quoting || escaping != Escape::Backslash)
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn writer_round_trip(rows in rows(), dialect in dialect()) {
+        let rows = rows
+            .iter()
+            .map(|row| row.iter().map(|field| field.as_str()).collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+        // without an escape a field with a quote cannot be written at all
+        let quote = char::from(dialect.quote);
+        if dialect.escaping == Escape::None && rows.iter().flatten().any(|field| field.contains(quote)) {
+            let mut writer = CsvWriter::new(Vec::new()).dialect(dialect);
+            let written = rows.iter().try_for_each(|row| writer.write_record(row));
+            prop_assert!(matches!(written, Err(CsvError::UnwritableQuote { .. })), "{:?}", written);
+            return Ok(());
+        }
+        let str = write(CsvWriter::new(Vec::new()).dialect(dialect), &rows);
+        // without quotes a record of one empty field can only be a blank line
+        let parser = CsvParser::from_string(str)
+            .dialect(dialect)
+            .skip_blank_lines(dialect.quoting);
+        let parsed = parse(parser);
+        prop_assert_eq!(parsed, rows);
+    }
+
+    #[test]
+    fn writer_round_trip_always_quoted(rows in rows()) {
+        let rows = rows
+            .iter()
+            .map(|row| row.iter().map(|field| field.as_str()).collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+        let writer = CsvWriter::new(Vec::new()).quote_style(QuoteStyle::Always);
+        let str = write(writer, &rows);
+        let parsed = parse(CsvParser::from_string(str));
+        prop_assert_eq!(parsed, rows);
+    }
+}
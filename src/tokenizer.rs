@@ -0,0 +1,660 @@
+use crate::dialect::unescape;
+use crate::{ByteRecord, CsvError, Dialect, Escape, Position, Result, Terminator};
+
+const OTHER: u8 = 0;
+const DELIMITER: u8 = 1;
+const QUOTE: u8 = 2;
+const TERMINATOR: u8 = 3;
+const ESCAPE: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    StartRecord,
+    StartField,
+    Unquoted,
+    UnquotedEscape,
+    Quoted,
+    QuotedEscape,
+    // a quote inside a quoted field, the next byte says if it closed the field
+    QuotedQuote,
+    // the record ended on '\r', a '\n' right after it belongs to it
+    RecordCr,
+    // dropping the rest of a malformed record
+    Skip,
+    SkipCr,
+    // dropping a line that is not a record, a comment, a blank line or one
+    // of the rows to skip
+    Line,
+    LineCr,
+}
+
+// what the tokenizer writes a record to. a `ByteRecord` copies the fields,
+// `SliceParser` only keeps where they are in the input
+pub(crate) trait Fields {
+    fn clear(&mut self);
+    // the next field starts at byte `at` of the input
+    fn start_field(&mut self, at: u64);
+    // `bytes` are in the input from byte `at` on
+    fn extend_field(&mut self, bytes: &[u8], at: u64);
+    // a byte that is not in the input as it is, what an escape stands for
+    fn push_byte(&mut self, b: u8);
+    fn end_field(&mut self, trim_space: bool, all_whitespace_empty: bool);
+    fn set_position(&mut self, position: Position);
+}
+
+// the tokenizer, it is handed whatever slice of input is available and keeps
+// its state between slices so a field or record can span any number of them.
+// bytes are looked up in a class table and runs of plain bytes are copied
+// into the record in one go. every parser in the crate is built on it
+#[derive(Debug, Clone)]
+pub struct CsvCore {
+    dialect: Dialect,
+    class: [u8; 256],
+    pub(crate) trim_space: bool,
+    pub(crate) all_whitespace_empty: bool,
+    pub(crate) comment: Option<u8>,
+    pub(crate) skip_rows: u64,
+    pub(crate) skip_blank_lines: bool,
+    // the lines dropped before the first record
+    preamble: Vec<String>,
+    line: Vec<u8>,
+    started: bool,
+    state: State,
+    // the record being read, counting the header and bad records
+    pub(crate) record: u64,
+    // where the first byte of the current input is, positions inside it are
+    // worked out only when they are needed
+    position: Position,
+    synced: usize,
+    last_cr: bool,
+    record_start: Position,
+    quote_start: Position,
+}
+
+impl CsvCore {
+    pub fn new(dialect: Dialect) -> Self {
+        let mut core = CsvCore {
+            dialect,
+            class: [OTHER; 256],
+            trim_space: false,
+            all_whitespace_empty: false,
+            comment: None,
+            skip_rows: 0,
+            skip_blank_lines: true,
+            preamble: Vec::new(),
+            line: Vec::new(),
+            started: false,
+            state: State::StartRecord,
+            record: 1,
+            position: Position::new(0, 1, 1, 0),
+            synced: 0,
+            last_cr: false,
+            record_start: Position::default(),
+            quote_start: Position::default(),
+        };
+        core.set_dialect(dialect);
+        core
+    }
+
+    pub fn trim_space(mut self, flag: bool) -> Self {
+        self.trim_space = flag;
+        self
+    }
+
+    pub fn all_whitespace_empty(mut self, flag: bool) -> Self {
+        self.all_whitespace_empty = flag;
+        self
+    }
+
+    // a line that starts with `comment` is dropped
+    pub fn comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    // drops the first `rows` lines whatever is in them
+    pub fn skip_rows(mut self, rows: u64) -> Self {
+        self.skip_rows = rows;
+        self
+    }
+
+    // on by default, when off an empty line is a record with one empty field
+    pub fn skip_blank_lines(mut self, flag: bool) -> Self {
+        self.skip_blank_lines = flag;
+        self
+    }
+
+    // the lines that were dropped before the first record, without their
+    // terminators
+    pub fn preamble(&self) -> &[String] {
+        &self.preamble
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+        self.class = [OTHER; 256];
+        // later classes win, in the same order the old byte parser checked them
+        if let Some(quote) = dialect.quote_byte() {
+            self.class[quote as usize] = QUOTE;
+        }
+        if let (Escape::Backslash, Some(escape)) = (dialect.escaping, dialect.escape_byte()) {
+            self.class[escape as usize] = ESCAPE;
+        }
+        match dialect.terminator {
+            Terminator::CRLF => {
+                self.class[b'\r' as usize] = TERMINATOR;
+                self.class[b'\n' as usize] = TERMINATOR;
+            }
+            Terminator::Any(b) => self.class[b as usize] = TERMINATOR,
+        }
+        self.class[dialect.delimiter as usize] = DELIMITER;
+    }
+
+    // the position of the next byte that has not been fed yet
+    pub fn position(&self) -> Position {
+        self.at(self.position)
+    }
+
+    // the byte the last record started at, after any skipped lines
+    pub(crate) fn record_start(&self) -> u64 {
+        self.record_start.byte()
+    }
+
+    pub(crate) fn is_terminator(&self, b: u8) -> bool {
+        self.class[b as usize] == TERMINATOR
+    }
+
+    pub fn skipping(&self) -> bool {
+        matches!(self.state, State::Skip | State::SkipCr)
+    }
+
+    // after an error, drop everything up to the end of the record
+    pub fn skip_record(&mut self) {
+        self.state = State::Skip;
+    }
+
+    fn at(&self, position: Position) -> Position {
+        Position::new(
+            self.record,
+            position.line(),
+            position.column(),
+            position.byte(),
+        )
+    }
+
+    // the byte in the whole input that `input[i]` is
+    fn byte_at(&self, i: usize) -> u64 {
+        self.position.byte() + (i - self.synced) as u64
+    }
+
+    // moves `position` up to `input[to]`, columns count chars so utf-8
+    // continuation bytes do not start a new one
+    fn sync(&mut self, input: &[u8], to: usize) {
+        if to == self.synced {
+            return;
+        }
+        let span = &input[self.synced..to];
+        let (mut line, mut column) = (self.position.line(), self.position.column());
+        let chars = |bytes: &[u8]| bytes.iter().filter(|b| **b as i8 >= -0x40).count();
+        match memchr::memrchr2(b'\r', b'\n', span) {
+            Some(last) => {
+                // "\r\n" is one line break, also when it is split between two inputs
+                let mut prev_cr = self.last_cr;
+                let mut prev = 0;
+                for i in memchr::memchr2_iter(b'\r', b'\n', &span[..=last]) {
+                    if !(span[i] == b'\n' && prev_cr && prev == i) {
+                        line += 1;
+                    }
+                    prev_cr = span[i] == b'\r';
+                    prev = i + 1;
+                }
+                column = 1 + chars(&span[last + 1..]) as u64;
+                self.last_cr = last + 1 == span.len() && span[last] == b'\r';
+            }
+            None if !span.is_empty() => {
+                column += chars(span) as u64;
+                self.last_cr = false;
+            }
+            None => {}
+        }
+        let byte = self.position.byte() + span.len() as u64;
+        self.position = Position::new(0, line, column, byte);
+        self.synced = to;
+    }
+
+    // reads as much of `input` as it can into `record`, returns how many
+    // bytes were used and whether `record` now holds a whole record. on an
+    // error the bytes up to the bad one are used
+    pub fn feed(&mut self, input: &[u8], record: &mut ByteRecord) -> (usize, Result<bool>) {
+        self.feed_into(input, record)
+    }
+
+    pub(crate) fn feed_into<F: Fields>(
+        &mut self,
+        input: &[u8],
+        record: &mut F,
+    ) -> (usize, Result<bool>) {
+        self.synced = 0;
+        let (used, result) = self.read(input, record);
+        self.sync(input, used);
+        self.synced = 0;
+        (used, result)
+    }
+
+    fn read<F: Fields>(&mut self, input: &[u8], record: &mut F) -> (usize, Result<bool>) {
+        let mut i = 0;
+        while i < input.len() {
+            let b = input[i];
+            match self.state {
+                State::StartRecord => {
+                    if self.skip_rows > 0
+                        || Some(b) == self.comment
+                        || (self.skip_blank_lines && self.is_terminator(b))
+                    {
+                        self.skip_rows = self.skip_rows.saturating_sub(1);
+                        self.state = State::Line;
+                        continue;
+                    }
+                    record.clear();
+                    self.sync(input, i);
+                    self.record_start = self.position;
+                    self.state = State::StartField;
+                }
+                State::StartField => {
+                    if self.class[b as usize] == QUOTE {
+                        self.sync(input, i);
+                        self.quote_start = self.position;
+                        record.start_field(self.byte_at(i + 1));
+                        self.state = State::Quoted;
+                        i += 1;
+                    } else {
+                        record.start_field(self.byte_at(i));
+                        self.state = State::Unquoted;
+                    }
+                }
+                State::Unquoted => {
+                    let end = match input[i..]
+                        .iter()
+                        .position(|b| self.class[*b as usize] != OTHER)
+                    {
+                        Some(end) => i + end,
+                        None => {
+                            record.extend_field(&input[i..], self.byte_at(i));
+                            return (input.len(), Ok(false));
+                        }
+                    };
+                    record.extend_field(&input[i..end], self.byte_at(i));
+                    i = end + 1;
+                    match self.class[input[end] as usize] {
+                        DELIMITER => {
+                            record.end_field(self.trim_space, self.all_whitespace_empty);
+                            // stay in the loop for the common case of one plain field after another
+                            if input
+                                .get(i)
+                                .is_none_or(|b| self.class[*b as usize] == QUOTE)
+                            {
+                                self.state = State::StartField;
+                            } else {
+                                record.start_field(self.byte_at(i));
+                            }
+                        }
+                        TERMINATOR => {
+                            record.end_field(self.trim_space, self.all_whitespace_empty);
+                            if self.end_record(input[end]) {
+                                return (i, Ok(self.finish_record(input, i, record)));
+                            }
+                        }
+                        ESCAPE => self.state = State::UnquotedEscape,
+                        _ => {
+                            self.sync(input, end);
+                            let position = self.at(self.position);
+                            return (i, Err(CsvError::UnexpectedQuote { position }));
+                        }
+                    }
+                }
+                State::UnquotedEscape => {
+                    record.push_byte(unescape(b));
+                    self.state = State::Unquoted;
+                    i += 1;
+                }
+                State::Quoted => {
+                    let found = match self.dialect.escape_byte() {
+                        Some(escape) => memchr::memchr2(self.dialect.quote, escape, &input[i..]),
+                        None => memchr::memchr(self.dialect.quote, &input[i..]),
+                    };
+                    let end = match found {
+                        Some(end) => i + end,
+                        None => {
+                            record.extend_field(&input[i..], self.byte_at(i));
+                            return (input.len(), Ok(false));
+                        }
+                    };
+                    record.extend_field(&input[i..end], self.byte_at(i));
+                    self.state = match input[end] == self.dialect.quote {
+                        true => State::QuotedQuote,
+                        false => State::QuotedEscape,
+                    };
+                    i = end + 1;
+                }
+                State::QuotedEscape => {
+                    record.push_byte(match self.dialect.escaping {
+                        Escape::Backslash => unescape(b),
+                        _ => b,
+                    });
+                    self.state = State::Quoted;
+                    i += 1;
+                }
+                State::QuotedQuote => match self.class[b as usize] {
+                    QUOTE if self.dialect.escaping == Escape::Doubled => {
+                        record.push_byte(b);
+                        self.state = State::Quoted;
+                        i += 1;
+                    }
+                    DELIMITER => {
+                        record.end_field(false, false);
+                        self.state = State::StartField;
+                        i += 1;
+                    }
+                    TERMINATOR => {
+                        record.end_field(false, false);
+                        i += 1;
+                        if self.end_record(b) {
+                            return (i, Ok(self.finish_record(input, i, record)));
+                        }
+                    }
+                    _ => {
+                        // reported at the closing quote, one byte back
+                        self.sync(input, i);
+                        let position = Position::new(
+                            self.record,
+                            self.position.line(),
+                            self.position.column() - 1,
+                            self.position.byte() - 1,
+                        );
+                        return (i, Err(CsvError::UnexpectedQuote { position }));
+                    }
+                },
+                State::RecordCr => {
+                    if b == b'\n' {
+                        i += 1;
+                    }
+                    return (i, Ok(self.finish_record(input, i, record)));
+                }
+                State::Skip => match input[i..].iter().position(|b| self.is_terminator(*b)) {
+                    Some(end) => {
+                        i += end + 1;
+                        match self.dialect.terminator == Terminator::CRLF && input[i - 1] == b'\r' {
+                            true => self.state = State::SkipCr,
+                            false => {
+                                self.end_skip();
+                                return (i, Ok(false));
+                            }
+                        }
+                    }
+                    None => return (input.len(), Ok(false)),
+                },
+                State::SkipCr => {
+                    if b == b'\n' {
+                        i += 1;
+                    }
+                    self.end_skip();
+                    return (i, Ok(false));
+                }
+                State::Line => match input[i..].iter().position(|b| self.is_terminator(*b)) {
+                    Some(end) => {
+                        self.keep_line(&input[i..i + end]);
+                        i += end + 1;
+                        match self.dialect.terminator == Terminator::CRLF && input[i - 1] == b'\r' {
+                            true => self.state = State::LineCr,
+                            false => self.end_line(),
+                        }
+                    }
+                    None => {
+                        self.keep_line(&input[i..]);
+                        return (input.len(), Ok(false));
+                    }
+                },
+                State::LineCr => {
+                    if b == b'\n' {
+                        i += 1;
+                    }
+                    self.end_line();
+                }
+            }
+        }
+        (i, Ok(false))
+    }
+
+    // like `feed_into` with nowhere to write the fields, for finding where
+    // records end. it reads on to the end of the first record that ends
+    // `until` bytes or more into `input`, returns how many bytes it used and
+    // if it got there. positions are only worked out once at the end, and a
+    // quote out of place is left for the parser that reads the fields
+    pub(crate) fn skim(&mut self, input: &[u8], until: usize) -> (usize, bool) {
+        self.synced = 0;
+        let mut i = 0;
+        let mut ended = false;
+        while i < input.len() && !(ended && i >= until) {
+            ended = false;
+            let b = input[i];
+            match self.state {
+                State::StartRecord => {
+                    if self.skip_rows > 0
+                        || Some(b) == self.comment
+                        || (self.skip_blank_lines && self.is_terminator(b))
+                    {
+                        self.skip_rows = self.skip_rows.saturating_sub(1);
+                        self.state = State::Line;
+                        continue;
+                    }
+                    self.state = State::StartField;
+                }
+                State::StartField => {
+                    self.state = match self.class[b as usize] == QUOTE {
+                        true => {
+                            i += 1;
+                            State::Quoted
+                        }
+                        false => State::Unquoted,
+                    };
+                }
+                // delimiters only matter when a quote starts the next field
+                State::Unquoted => {
+                    let end = match input[i..]
+                        .iter()
+                        .position(|b| self.class[*b as usize] > DELIMITER)
+                    {
+                        Some(end) => i + end,
+                        None => {
+                            if input.last() == Some(&self.dialect.delimiter) {
+                                self.state = State::StartField;
+                            }
+                            i = input.len();
+                            break;
+                        }
+                    };
+                    i = end + 1;
+                    match self.class[input[end] as usize] {
+                        QUOTE if end > 0 && input[end - 1] == self.dialect.delimiter => {
+                            self.state = State::Quoted;
+                        }
+                        TERMINATOR => ended = self.skim_record_end(input[end]),
+                        ESCAPE => self.state = State::UnquotedEscape,
+                        _ => {}
+                    }
+                }
+                State::UnquotedEscape | State::QuotedEscape => {
+                    self.state = match self.state {
+                        State::UnquotedEscape => State::Unquoted,
+                        _ => State::Quoted,
+                    };
+                    i += 1;
+                }
+                State::Quoted => {
+                    let found = match self.dialect.escape_byte() {
+                        Some(escape) => memchr::memchr2(self.dialect.quote, escape, &input[i..]),
+                        None => memchr::memchr(self.dialect.quote, &input[i..]),
+                    };
+                    let end = match found {
+                        Some(end) => i + end,
+                        None => {
+                            i = input.len();
+                            break;
+                        }
+                    };
+                    self.state = match input[end] == self.dialect.quote {
+                        true => State::QuotedQuote,
+                        false => State::QuotedEscape,
+                    };
+                    i = end + 1;
+                }
+                State::QuotedQuote => match self.class[b as usize] {
+                    QUOTE if self.dialect.escaping == Escape::Doubled => {
+                        self.state = State::Quoted;
+                        i += 1;
+                    }
+                    DELIMITER => {
+                        self.state = State::StartField;
+                        i += 1;
+                    }
+                    TERMINATOR => {
+                        i += 1;
+                        ended = self.skim_record_end(b);
+                    }
+                    _ => self.state = State::Unquoted,
+                },
+                State::RecordCr | State::SkipCr => {
+                    if b == b'\n' {
+                        i += 1;
+                    }
+                    self.end_skip();
+                    ended = true;
+                }
+                State::Skip | State::Line => {
+                    match input[i..].iter().position(|b| self.is_terminator(*b)) {
+                        Some(end) => {
+                            if self.state == State::Line {
+                                self.keep_line(&input[i..i + end]);
+                            }
+                            i += end + 1;
+                            let cr = self.dialect.terminator == Terminator::CRLF
+                                && input[i - 1] == b'\r';
+                            match (self.state, cr) {
+                                (State::Line, true) => self.state = State::LineCr,
+                                (State::Line, false) => self.end_line(),
+                                (_, true) => self.state = State::SkipCr,
+                                (_, false) => {
+                                    self.end_skip();
+                                    ended = true;
+                                }
+                            }
+                        }
+                        None => {
+                            if self.state == State::Line {
+                                self.keep_line(&input[i..]);
+                            }
+                            i = input.len();
+                        }
+                    }
+                }
+                State::LineCr => {
+                    if b == b'\n' {
+                        i += 1;
+                    }
+                    self.end_line();
+                }
+            }
+        }
+        self.sync(input, i);
+        self.synced = 0;
+        (i, ended && i >= until)
+    }
+
+    // a record ended on `b` without being read, counted like a skipped one
+    fn skim_record_end(&mut self, b: u8) -> bool {
+        if !self.end_record(b) {
+            return false;
+        }
+        self.end_skip();
+        true
+    }
+
+    // the end of the input, completes whatever record is left
+    pub fn finish(&mut self, record: &mut ByteRecord) -> Result<bool> {
+        self.finish_into(record)
+    }
+
+    pub(crate) fn finish_into<F: Fields>(&mut self, record: &mut F) -> Result<bool> {
+        match self.state {
+            State::StartRecord => return Ok(false),
+            State::StartField => record.start_field(self.position.byte()),
+            State::Unquoted => {}
+            // an escape at the very end has nothing to escape
+            State::UnquotedEscape => record.push_byte(self.dialect.escape_byte().unwrap_or(b'\\')),
+            State::Quoted | State::QuotedEscape => {
+                return Err(CsvError::UnterminatedQuote {
+                    position: self.at(self.quote_start),
+                });
+            }
+            State::QuotedQuote | State::RecordCr => {}
+            State::Skip | State::SkipCr => {
+                self.end_skip();
+                return Ok(false);
+            }
+            State::Line | State::LineCr => {
+                self.end_line();
+                return Ok(false);
+            }
+        }
+        match self.state {
+            State::QuotedQuote => record.end_field(false, false),
+            State::RecordCr => {}
+            _ => record.end_field(self.trim_space, self.all_whitespace_empty),
+        }
+        Ok(self.finish_record(&[], 0, record))
+    }
+
+    // `b` is the terminator that ended the record, returns false when it
+    // has to wait for the byte after a '\r'
+    fn end_record(&mut self, b: u8) -> bool {
+        if self.dialect.terminator == Terminator::CRLF && b == b'\r' {
+            self.state = State::RecordCr;
+            return false;
+        }
+        true
+    }
+
+    fn finish_record<F: Fields>(&mut self, input: &[u8], i: usize, record: &mut F) -> bool {
+        self.sync(input, i);
+        self.started = true;
+        record.set_position(self.at(self.record_start));
+        self.record += 1;
+        self.state = State::StartRecord;
+        true
+    }
+
+    fn end_skip(&mut self) {
+        self.started = true;
+        self.record += 1;
+        self.state = State::StartRecord;
+    }
+
+    fn keep_line(&mut self, bytes: &[u8]) {
+        if !self.started {
+            self.line.extend_from_slice(bytes);
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.started {
+            let line = std::mem::take(&mut self.line);
+            self.preamble
+                .push(String::from_utf8_lossy(&line).into_owned());
+        }
+        self.state = State::StartRecord;
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::{CsvError, Dialect, Escape, Position, Result, Terminator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    // only fields that would not read back the same, the default
+    Necessary,
+    Always,
+    NonNumeric,
+    Never,
+}
+
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    writer: io::BufWriter<W>,
+    dialect: Dialect,
+    quote_style: QuoteStyle,
+    started: bool,
+    // records written so far, the header included
+    pub(crate) record: u64,
+    #[cfg(feature = "serde")]
+    pub(crate) has_header: bool,
+    #[cfg(feature = "serde")]
+    pub(crate) header_separator: String,
+    // the column names of the first value that was serialized
+    #[cfg(feature = "serde")]
+    pub(crate) columns: Vec<String>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvWriter {
+            writer: io::BufWriter::new(writer),
+            dialect: Dialect::default(),
+            quote_style: QuoteStyle::Necessary,
+            started: false,
+            record: 0,
+            #[cfg(feature = "serde")]
+            has_header: true,
+            #[cfg(feature = "serde")]
+            header_separator: String::from("."),
+            #[cfg(feature = "serde")]
+            columns: Vec::new(),
+        }
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.dialect.delimiter = delimiter;
+        self
+    }
+
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.dialect.quote = quote;
+        self
+    }
+
+    pub fn escape(mut self, escape: Option<u8>) -> Self {
+        self.dialect.escape = escape;
+        self
+    }
+
+    pub fn escaping(mut self, escaping: Escape) -> Self {
+        self.dialect.escaping = escaping;
+        self
+    }
+
+    pub fn quoting(mut self, flag: bool) -> Self {
+        self.dialect.quoting = flag;
+        self
+    }
+
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.dialect.terminator = terminator;
+        self
+    }
+
+    pub fn quote_style(mut self, style: QuoteStyle) -> Self {
+        self.quote_style = style;
+        self
+    }
+
+    pub fn write_record<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut len = 0;
+        let mut empty = false;
+        for (i, field) in record.into_iter().enumerate() {
+            let field = field.as_ref();
+            let quoted = self.is_quoted(field);
+            // without an escape a quote cannot be written inside quotes,
+            // and the fields before this one are already written
+            if quoted
+                && self.dialect.escaping == Escape::None
+                && field.contains(&self.dialect.quote)
+            {
+                return Err(CsvError::UnwritableQuote {
+                    field: i as u64,
+                    position: Position::new(self.record + 1, 0, 0, 0),
+                });
+            }
+            if i > 0 {
+                self.writer.write_all(&[self.dialect.delimiter])?;
+            }
+            empty = field.is_empty();
+            len += 1;
+            self.write_field(field, quoted)?;
+        }
+        // a lone empty field would be a blank line, which the parser skips
+        if len == 1 && empty && self.dialect.quoting && self.quote_style == QuoteStyle::Necessary {
+            self.writer
+                .write_all(&[self.dialect.quote, self.dialect.quote])?;
+        }
+        match self.dialect.terminator {
+            Terminator::CRLF => self.writer.write_all(b"\r\n")?,
+            Terminator::Any(terminator) => self.writer.write_all(&[terminator])?,
+        }
+        self.record += 1;
+        Ok(())
+    }
+
+    fn is_quoted(&self, field: &[u8]) -> bool {
+        self.dialect.quoting
+            && match self.quote_style {
+                QuoteStyle::Necessary => self.needs_quotes(field),
+                QuoteStyle::Always => true,
+                QuoteStyle::NonNumeric => !is_numeric(field) || self.needs_quotes(field),
+                QuoteStyle::Never => false,
+            }
+    }
+
+    fn write_field(&mut self, field: &[u8], quoted: bool) -> Result<()> {
+        let first = !std::mem::replace(&mut self.started, true);
+        if !quoted {
+            return self.write_unquoted(field, first);
+        }
+
+        let quote = self.dialect.quote;
+        self.writer.write_all(&[quote])?;
+        let mut start = 0;
+        for (i, b) in field.iter().enumerate() {
+            let escape = match self.dialect.escape_byte() {
+                Some(escape) if *b == quote || *b == escape => escape,
+                None if *b == quote => quote,
+                _ => continue,
+            };
+            self.writer.write_all(&field[start..i])?;
+            self.writer.write_all(&[escape])?;
+            start = i;
+        }
+        self.writer.write_all(&field[start..])?;
+        self.writer.write_all(&[quote])?;
+        Ok(())
+    }
+
+    // with backslash escapes anything the parser would stop at is escaped,
+    // otherwise the field is written as is. a leading BOM at the very start
+    // gets its first byte escaped so the parser keeps it
+    fn write_unquoted(&mut self, field: &[u8], first: bool) -> Result<()> {
+        if self.dialect.escaping != Escape::Backslash {
+            self.writer.write_all(field)?;
+            return Ok(());
+        }
+        let bom = first && field.starts_with(b"\xef\xbb\xbf");
+        let escape = self.dialect.escape_byte().unwrap_or(b'\\');
+        let mut start = 0;
+        for (i, b) in field.iter().enumerate() {
+            if (bom && i == 0)
+                || *b == escape
+                || Some(*b) == self.dialect.quote_byte()
+                || self.is_special(*b)
+            {
+                self.writer.write_all(&field[start..i])?;
+                self.writer.write_all(&[escape])?;
+                start = i;
+            }
+        }
+        self.writer.write_all(&field[start..])?;
+        Ok(())
+    }
+
+    fn is_special(&self, b: u8) -> bool {
+        b == self.dialect.delimiter
+            || b == b'\r'
+            || b == b'\n'
+            || self.dialect.terminator == Terminator::Any(b)
+    }
+
+    fn needs_quotes(&self, field: &[u8]) -> bool {
+        // a leading BOM at the very start would be dropped by the parser
+        if !self.started && field.starts_with(b"\xef\xbb\xbf") {
+            return true;
+        }
+        field
+            .iter()
+            .any(|b| *b == self.dialect.quote || self.is_special(*b))
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> Result<W> {
+        match self.writer.into_inner() {
+            Ok(writer) => Ok(writer),
+            Err(err) => Err(err.into_error().into()),
+        }
+    }
+}
+
+impl CsvWriter<File> {
+    pub fn from_path(file_path: &PathBuf) -> io::Result<Self> {
+        let file = File::create(file_path)?;
+        Ok(Self::new(file))
+    }
+}
+
+fn is_numeric(field: &[u8]) -> bool {
+    let text = match std::str::from_utf8(field) {
+        Ok(text) => text,
+        Err(_) => return false,
+    };
+    field
+        .iter()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.' | b'e' | b'E'))
+        && text.parse::<f64>().is_ok()
+}